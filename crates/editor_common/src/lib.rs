@@ -0,0 +1,135 @@
+use nih_plug::prelude::*;
+use nih_plug_egui::egui::{self, Align2, Color32, FontId, Pos2, Rect, Stroke};
+use nih_plug_egui::widgets::ParamSlider;
+
+pub const WINDOW_WIDTH: u32 = 720;
+pub const WINDOW_HEIGHT: u32 = 420;
+pub const MIN_WIDTH: f32 = 480.0;
+pub const MIN_HEIGHT: f32 = 300.0;
+
+/// Frequency range of the plots
+pub const MIN_FREQ: f32 = 20.0;
+pub const MAX_FREQ: f32 = 20_000.0;
+
+pub const BACKGROUND: Color32 = Color32::from_rgb(24, 24, 28);
+pub const GRID: Color32 = Color32::from_rgb(48, 48, 54);
+
+/// Labelled slider for one parameter
+pub fn param_slider<P: Param>(ui: &mut egui::Ui, label: &str, param: &P, setter: &ParamSetter) {
+    ui.label(label);
+    ui.add(ParamSlider::for_param(param, setter).with_width(90.0));
+}
+
+/// Log frequency across and level in dB up a plot's rectangle
+#[derive(Clone, Copy)]
+pub struct PlotAxes {
+    pub rect: Rect,
+    pub min_db: f32,
+    pub max_db: f32,
+}
+
+impl PlotAxes {
+    pub fn new(rect: Rect, min_db: f32, max_db: f32) -> Self {
+        Self {
+            rect,
+            min_db,
+            max_db,
+        }
+    }
+
+    /// The same frequency axis over another level range
+    pub fn with_db_range(self, min_db: f32, max_db: f32) -> Self {
+        Self::new(self.rect, min_db, max_db)
+    }
+
+    pub fn x_to_freq(&self, x: f32) -> f32 {
+        let t = ((x - self.rect.left()) / self.rect.width()).clamp(0.0, 1.0);
+        MIN_FREQ * (MAX_FREQ / MIN_FREQ).powf(t)
+    }
+
+    pub fn freq_to_x(&self, freq: f32) -> f32 {
+        self.rect.left() + (freq / MIN_FREQ).ln() / (MAX_FREQ / MIN_FREQ).ln() * self.rect.width()
+    }
+
+    pub fn y_to_db(&self, y: f32) -> f32 {
+        let t = ((self.rect.bottom() - y) / self.rect.height()).clamp(0.0, 1.0);
+        self.min_db + t * (self.max_db - self.min_db)
+    }
+
+    /// Levels outside the range are pinned to its edges
+    pub fn db_to_y(&self, db: f32) -> f32 {
+        let t = (db.clamp(self.min_db, self.max_db) - self.min_db) / (self.max_db - self.min_db);
+        self.rect.bottom() - t * self.rect.height()
+    }
+
+    /// Points of `level_db` across the plot, one per logical pixel
+    pub fn curve(&self, level_db: impl Fn(f32) -> f32) -> Vec<Pos2> {
+        (0..=self.rect.width() as usize)
+            .map(|i| {
+                let x = self.rect.left() + i as f32;
+                Pos2::new(x, self.db_to_y(level_db(self.x_to_freq(x))))
+            })
+            .collect()
+    }
+}
+
+/// Decade grid lines and level lines every `db_step` dB, with labels
+pub fn draw_grid(painter: &egui::Painter, axes: &PlotAxes, db_step: f32) {
+    let rect = axes.rect;
+    let stroke = Stroke::new(1.0, GRID);
+    let font = FontId::proportional(11.0);
+
+    for (freq, label) in [(100.0, "100"), (1000.0, "1k"), (10_000.0, "10k")] {
+        let x = axes.freq_to_x(freq);
+        painter.line_segment(
+            [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+            stroke,
+        );
+        painter.text(
+            Pos2::new(x + 3.0, rect.bottom() - 3.0),
+            Align2::LEFT_BOTTOM,
+            label,
+            font.clone(),
+            Color32::GRAY,
+        );
+    }
+
+    let mut db = axes.min_db + db_step;
+    while db < axes.max_db {
+        let y = axes.db_to_y(db);
+        painter.line_segment(
+            [Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)],
+            stroke,
+        );
+        painter.text(
+            Pos2::new(rect.left() + 3.0, y - 2.0),
+            Align2::LEFT_BOTTOM,
+            format!("{:+} dB", db),
+            font.clone(),
+            Color32::GRAY,
+        );
+        db += db_step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nih_plug_egui::egui::Vec2;
+
+    #[test]
+    fn test_axes_map_both_ways() {
+        let rect = Rect::from_min_size(Pos2::new(10.0, 20.0), Vec2::new(600.0, 300.0));
+        let axes = PlotAxes::new(rect, -48.0, 24.0);
+
+        assert!((axes.freq_to_x(MIN_FREQ) - rect.left()).abs() < 1e-3);
+        assert!((axes.freq_to_x(MAX_FREQ) - rect.right()).abs() < 1e-3);
+        for freq in [20.0, 100.0, 1000.0, 15_000.0] {
+            assert!((axes.x_to_freq(axes.freq_to_x(freq)) / freq - 1.0).abs() < 1e-4);
+        }
+
+        assert_eq!(axes.db_to_y(-48.0), rect.bottom());
+        assert_eq!(axes.db_to_y(100.0), rect.top());
+        assert!((axes.y_to_db(axes.db_to_y(-6.0)) + 6.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,136 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use filter_dsp::filter::{FilterChain, FilterSlope, FilterType};
+use filter_dsp::lanes::FilterChainLanes;
+
+const SAMPLE_RATE: f32 = 48000.0;
+const BLOCK_SIZE: usize = 512;
+/// Longest run of frames the plugins filter at once
+const RUN_SIZE: usize = 64;
+
+fn chain() -> FilterChain {
+    let mut chain = FilterChain::default();
+    chain.update_coefficients(
+        SAMPLE_RATE,
+        120.0,
+        std::f32::consts::FRAC_1_SQRT_2,
+        0.0,
+        FilterType::HighPass,
+        FilterSlope::Slope24dB,
+    );
+    chain
+}
+
+fn signal() -> Vec<f32> {
+    (0..BLOCK_SIZE)
+        .map(|n| ((n * 7919) % 2048) as f32 / 1024.0 - 1.0)
+        .collect()
+}
+
+/// Fill the first `channels` lanes of every frame, leaving padding lanes silent
+fn fill_frames<const L: usize>(frames: &mut [[f32; L]], input: &[f32], channels: usize) {
+    for (frame, &x) in frames.iter_mut().zip(input) {
+        *frame = std::array::from_fn(|lane| if lane < channels { x } else { 0.0 });
+    }
+}
+
+/// Run `N` channels through the scalar chain, sample by sample
+fn scalar_per_sample<const N: usize>(filters: &mut [FilterChain; N], channels: &mut [Vec<f32>; N]) {
+    for i in 0..BLOCK_SIZE {
+        for (filter, channel) in filters.iter_mut().zip(channels.iter_mut()) {
+            channel[i] = filter.process(channel[i]);
+        }
+    }
+}
+
+/// Run `N` channels through the scalar chain, one block per channel
+fn scalar_block<const N: usize>(filters: &mut [FilterChain; N], channels: &mut [Vec<f32>; N]) {
+    for (filter, channel) in filters.iter_mut().zip(channels.iter_mut()) {
+        filter.process_block(channel);
+    }
+}
+
+/// Compare `N` scalar channels against the same channels in `L` SIMD lanes
+fn bench_channels<const N: usize, const L: usize>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements((BLOCK_SIZE * N) as u64));
+
+    let input = signal();
+    let chain = chain();
+
+    group.bench_function("scalar_per_sample", |b| {
+        let mut filters = [chain; N];
+        let mut channels: [Vec<f32>; N] = std::array::from_fn(|_| input.clone());
+        b.iter(|| {
+            for channel in channels.iter_mut() {
+                channel.copy_from_slice(&input);
+            }
+            scalar_per_sample(&mut filters, &mut channels);
+            black_box(&channels);
+        })
+    });
+
+    group.bench_function("scalar_block", |b| {
+        let mut filters = [chain; N];
+        let mut channels: [Vec<f32>; N] = std::array::from_fn(|_| input.clone());
+        b.iter(|| {
+            for channel in channels.iter_mut() {
+                channel.copy_from_slice(&input);
+            }
+            scalar_block(&mut filters, &mut channels);
+            black_box(&channels);
+        })
+    });
+
+    group.bench_function("lanes_per_frame", |b| {
+        let mut lanes = FilterChainLanes::<L>::default();
+        lanes.copy_coefficients(&chain);
+        let mut frames = vec![[0.0; L]; BLOCK_SIZE];
+        b.iter(|| {
+            fill_frames(&mut frames, &input, N);
+            for frame in frames.iter_mut() {
+                *frame = lanes.process(*frame);
+            }
+            black_box(&frames);
+        })
+    });
+
+    group.bench_function("lanes_block", |b| {
+        let mut lanes = FilterChainLanes::<L>::default();
+        lanes.copy_coefficients(&chain);
+        let mut frames = vec![[0.0; L]; BLOCK_SIZE];
+        b.iter(|| {
+            fill_frames(&mut frames, &input, N);
+            lanes.process_block(&mut frames);
+            black_box(&frames);
+        })
+    });
+
+    group.bench_function("lanes_block_runs", |b| {
+        let mut lanes = FilterChainLanes::<L>::default();
+        lanes.copy_coefficients(&chain);
+        let mut frames = vec![[0.0; L]; BLOCK_SIZE];
+        b.iter(|| {
+            fill_frames(&mut frames, &input, N);
+            for run in frames.chunks_mut(RUN_SIZE) {
+                lanes.process_block(run);
+            }
+            black_box(&frames);
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_stereo(c: &mut Criterion) {
+    bench_channels::<2, 2>(c, "stereo");
+    // Stereo padded out to a full 128-bit register
+    bench_channels::<2, 4>(c, "stereo_padded");
+}
+
+fn bench_multichannel(c: &mut Criterion) {
+    bench_channels::<4, 4>(c, "quad");
+    bench_channels::<8, 8>(c, "octo");
+}
+
+criterion_group!(benches, bench_stereo, bench_multichannel);
+criterion_main!(benches);
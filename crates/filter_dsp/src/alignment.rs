@@ -0,0 +1,296 @@
+use std::f64::consts::PI;
+
+/// Maximum number of second-order sections in a prototype
+pub const MAX_SECTIONS: usize = 8;
+
+/// Maximum prototype order
+const MAX_ORDER: usize = 2 * MAX_SECTIONS;
+
+/// Pole alignment of a cascaded filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterAlignment {
+    #[default]
+    Butterworth,
+    LinkwitzRiley,
+    Bessel,
+    Chebyshev,
+}
+
+/// Second-order section of a normalized low-pass prototype
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Section {
+    /// Pole frequency relative to the nominal cutoff
+    pub freq: f32,
+    pub q: f32,
+}
+
+/// Low-pass prototype normalized so the nominal cutoff sits at w = 1
+/// (-3 dB, or -6 dB for Linkwitz-Riley). High-pass stages use the
+/// reciprocal pole frequencies.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Prototype {
+    /// Real pole frequency relative to the nominal cutoff, for odd orders
+    pub first_order: Option<f32>,
+    sections: [Section; MAX_SECTIONS],
+    num_sections: usize,
+}
+
+impl Prototype {
+    /// Design the prototype for an alignment and filter order.
+    /// Linkwitz-Riley only exists for even orders and falls back to Butterworth.
+    pub fn new(alignment: FilterAlignment, order: usize, ripple_db: f32) -> Self {
+        let order = order.clamp(1, MAX_ORDER);
+        let mut poles = [(0.0, 0.0); MAX_ORDER];
+
+        let (num_poles, scale) = match alignment {
+            FilterAlignment::LinkwitzRiley if order.is_multiple_of(2) => {
+                let half = butterworth_poles(order / 2, &mut poles);
+                for i in 0..half {
+                    poles[half + i] = poles[i];
+                }
+                (2 * half, 1.0)
+            }
+            FilterAlignment::Butterworth | FilterAlignment::LinkwitzRiley => {
+                (butterworth_poles(order, &mut poles), 1.0)
+            }
+            FilterAlignment::Bessel => bessel_poles(order, &mut poles),
+            FilterAlignment::Chebyshev => chebyshev_poles(order, ripple_db as f64, &mut poles),
+        };
+
+        Self::from_poles(&poles[..num_poles], scale)
+    }
+
+    /// Second-order sections, sorted by ascending Q
+    pub fn sections(&self) -> &[Section] {
+        &self.sections[..self.num_sections]
+    }
+
+    /// Build sections from upper half-plane poles, dividing frequencies by `scale`
+    fn from_poles(poles: &[(f64, f64)], scale: f64) -> Self {
+        let mut prototype = Self::default();
+        let mut pending_real: Option<f64> = None;
+
+        for &(re, im) in poles {
+            if im.abs() > 1e-9 {
+                let w0 = re.hypot(im);
+                prototype.push_section(w0 / scale, w0 / (-2.0 * re));
+            } else if let Some(other) = pending_real.take() {
+                // Pair two real poles into one biquad
+                let w0 = (other * -re).sqrt();
+                prototype.push_section(w0 / scale, w0 / (other - re));
+            } else {
+                pending_real = Some(-re);
+            }
+        }
+
+        prototype.first_order = pending_real.map(|w| (w / scale) as f32);
+        prototype.sections[..prototype.num_sections]
+            .sort_by(|a, b| a.q.partial_cmp(&b.q).unwrap_or(std::cmp::Ordering::Equal));
+        prototype
+    }
+
+    fn push_section(&mut self, freq: f64, q: f64) {
+        if self.num_sections < MAX_SECTIONS {
+            self.sections[self.num_sections] = Section {
+                freq: freq as f32,
+                q: q as f32,
+            };
+            self.num_sections += 1;
+        }
+    }
+}
+
+/// Butterworth poles on the unit circle (upper half-plane and real axis)
+fn butterworth_poles(order: usize, poles: &mut [(f64, f64)]) -> usize {
+    let mut count = 0;
+    for k in 0..order.div_ceil(2) {
+        let theta = PI * (2 * k + 1) as f64 / (2 * order) as f64;
+        poles[count] = (-theta.sin(), theta.cos());
+        count += 1;
+    }
+    count
+}
+
+/// Chebyshev type I poles, scaled so the response is -3 dB at w = 1
+/// relative to the passband gain the unity-gain sections produce
+fn chebyshev_poles(order: usize, ripple_db: f64, poles: &mut [(f64, f64)]) -> (usize, f64) {
+    let n = order as f64;
+    let epsilon = (10.0f64.powf(ripple_db.max(0.01) / 10.0) - 1.0).sqrt();
+    let v = (1.0 / epsilon).asinh() / n;
+
+    let mut count = 0;
+    for k in 0..order.div_ceil(2) {
+        let theta = PI * (2 * k + 1) as f64 / (2.0 * n);
+        poles[count] = (-v.sinh() * theta.sin(), v.cosh() * theta.cos());
+        count += 1;
+    }
+
+    // Even orders start at a ripple trough, so the sections' unity DC gain
+    // lifts the whole response by the ripple
+    let target = if order.is_multiple_of(2) {
+        (1.0 + 2.0 * epsilon * epsilon).sqrt() / epsilon
+    } else {
+        1.0 / epsilon
+    };
+    (count, (target.acosh() / n).cosh())
+}
+
+/// Bessel poles from the roots of the reverse Bessel polynomial, scaled so
+/// the response is -3 dB at w = 1
+fn bessel_poles(order: usize, poles: &mut [(f64, f64)]) -> (usize, f64) {
+    // Coefficients a_k = (2n - k)! / (2^(n - k) k! (n - k)!), ascending, monic
+    let mut coeffs = [0.0; MAX_ORDER + 1];
+    for (k, coeff) in coeffs.iter_mut().enumerate().take(order + 1) {
+        *coeff = factorial(2 * order - k)
+            / (2.0f64.powi((order - k) as i32) * factorial(k) * factorial(order - k));
+    }
+    let coeffs = &coeffs[..=order];
+
+    let mut roots = [(0.0, 0.0); MAX_ORDER];
+    polynomial_roots(coeffs, &mut roots[..order]);
+
+    let mut count = 0;
+    for &root in &roots[..order] {
+        if root.1 >= -1e-9 {
+            poles[count] = (root.0, root.1.max(0.0));
+            count += 1;
+        }
+    }
+
+    // |H(jw)|^2 = a0^2 / |theta(jw)|^2 falls monotonically; bisect for 1/2
+    let magnitude_sq = |w: f64| {
+        let value = evaluate(coeffs, (0.0, w));
+        coeffs[0] * coeffs[0] / (value.0 * value.0 + value.1 * value.1)
+    };
+    let mut hi = 1.0;
+    while magnitude_sq(hi) > 0.5 {
+        hi *= 2.0;
+    }
+    let mut lo = 0.0;
+    for _ in 0..60 {
+        let mid = 0.5 * (lo + hi);
+        if magnitude_sq(mid) > 0.5 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    (count, 0.5 * (lo + hi))
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|i| i as f64).product()
+}
+
+/// Evaluate a real polynomial (ascending coefficients) at a complex point
+fn evaluate(coeffs: &[f64], z: (f64, f64)) -> (f64, f64) {
+    coeffs.iter().rev().fold((0.0, 0.0), |acc, &c| {
+        let product = complex_mul(acc, z);
+        (product.0 + c, product.1)
+    })
+}
+
+/// Durand-Kerner root finding for a monic polynomial (ascending coefficients)
+fn polynomial_roots(coeffs: &[f64], roots: &mut [(f64, f64)]) {
+    let radius = 1.0
+        + coeffs
+            .iter()
+            .map(|c| c.abs())
+            .fold(0.0, f64::max)
+            .powf(1.0 / roots.len() as f64);
+    let mut seed = (0.4, 0.9);
+    for root in roots.iter_mut() {
+        *root = (seed.0 * radius, seed.1 * radius);
+        seed = complex_mul(seed, (0.4, 0.9));
+    }
+
+    for _ in 0..500 {
+        let mut max_step: f64 = 0.0;
+        for i in 0..roots.len() {
+            let mut denominator = (1.0, 0.0);
+            for j in 0..roots.len() {
+                if i != j {
+                    let diff = (roots[i].0 - roots[j].0, roots[i].1 - roots[j].1);
+                    denominator = complex_mul(denominator, diff);
+                }
+            }
+            let step = complex_div(evaluate(coeffs, roots[i]), denominator);
+            roots[i] = (roots[i].0 - step.0, roots[i].1 - step.1);
+            max_step = max_step.max(step.0.hypot(step.1));
+        }
+        if max_step < 1e-14 {
+            break;
+        }
+    }
+}
+
+fn complex_mul(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+fn complex_div(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    let denominator = b.0 * b.0 + b.1 * b.1;
+    (
+        (a.0 * b.0 + a.1 * b.1) / denominator,
+        (a.1 * b.0 - a.0 * b.1) / denominator,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_butterworth_q() {
+        let prototype = Prototype::new(FilterAlignment::Butterworth, 4, 0.0);
+        let sections = prototype.sections();
+
+        assert_eq!(sections.len(), 2);
+        assert!(prototype.first_order.is_none());
+        assert!((sections[0].q - 0.5412).abs() < 1e-3);
+        assert!((sections[1].q - 1.3066).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_linkwitz_riley_sections() {
+        let lr2 = Prototype::new(FilterAlignment::LinkwitzRiley, 2, 0.0);
+        assert!((lr2.sections()[0].q - 0.5).abs() < 1e-4);
+
+        let lr4 = Prototype::new(FilterAlignment::LinkwitzRiley, 4, 0.0);
+        for section in lr4.sections() {
+            assert!((section.q - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_high_orders_are_stable() {
+        for alignment in [
+            FilterAlignment::Butterworth,
+            FilterAlignment::LinkwitzRiley,
+            FilterAlignment::Bessel,
+            FilterAlignment::Chebyshev,
+        ] {
+            for order in [6, 8, 12, 16] {
+                let prototype = Prototype::new(alignment, order, 0.5);
+                assert_eq!(prototype.sections().len(), order / 2);
+                for section in prototype.sections() {
+                    assert!(section.freq.is_finite() && section.freq > 0.0);
+                    assert!(section.q.is_finite() && section.q >= 0.5 - 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bessel_matches_table() {
+        // -3 dB normalized Bessel, 4th order
+        let prototype = Prototype::new(FilterAlignment::Bessel, 4, 0.0);
+        let sections = prototype.sections();
+
+        assert!((sections[0].freq - 1.4302).abs() < 1e-3);
+        assert!((sections[0].q - 0.5219).abs() < 1e-3);
+        assert!((sections[1].freq - 1.6034).abs() < 1e-3);
+        assert!((sections[1].q - 0.8055).abs() < 1e-3);
+    }
+}
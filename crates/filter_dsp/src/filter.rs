@@ -0,0 +1,1909 @@
+use num_complex::Complex64;
+use num_traits::{Float, FloatConst, NumAssign};
+use std::f32::consts::FRAC_1_SQRT_2;
+
+pub use crate::alignment::FilterAlignment;
+use crate::alignment::{Prototype, MAX_SECTIONS};
+use crate::svf::{OnePoleState, SvfState};
+
+/// Filter slope options
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterSlope {
+    Slope6dB,
+    #[default]
+    Slope12dB,
+    Slope18dB,
+    Slope24dB,
+    Slope36dB,
+    Slope48dB,
+    Slope72dB,
+    Slope96dB,
+}
+
+impl FilterSlope {
+    /// Filter order of the slope
+    pub fn order(self) -> usize {
+        match self {
+            FilterSlope::Slope6dB => 1,
+            FilterSlope::Slope12dB => 2,
+            FilterSlope::Slope18dB => 3,
+            FilterSlope::Slope24dB => 4,
+            FilterSlope::Slope36dB => 6,
+            FilterSlope::Slope48dB => 8,
+            FilterSlope::Slope72dB => 12,
+            FilterSlope::Slope96dB => 16,
+        }
+    }
+}
+
+/// Filter response types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterType {
+    #[default]
+    HighPass,
+    LowPass,
+    BandPass,
+    Notch,
+    Peak,
+    LowShelf,
+    HighShelf,
+    AllPass,
+}
+
+impl FilterType {
+    /// Whether the response has a first-order form usable for the odd slopes
+    pub fn has_first_order(self) -> bool {
+        matches!(
+            self,
+            FilterType::HighPass
+                | FilterType::LowPass
+                | FilterType::LowShelf
+                | FilterType::HighShelf
+                | FilterType::AllPass
+        )
+    }
+
+    /// Whether the cascade follows the selected pole alignment
+    pub fn uses_alignment(self) -> bool {
+        matches!(
+            self,
+            FilterType::HighPass | FilterType::LowPass | FilterType::AllPass
+        )
+    }
+}
+
+/// Floating point type a filter's coefficient math and state run in.
+/// Parameters and sample rates stay f32 at the API boundary.
+pub trait Sample: Float + FloatConst + NumAssign + Default {
+    fn from_f32(x: f32) -> Self;
+    fn from_f64(x: f64) -> Self;
+    fn as_f32(self) -> f32;
+    fn as_f64(self) -> f64;
+}
+
+impl Sample for f32 {
+    #[inline]
+    fn from_f32(x: f32) -> Self {
+        x
+    }
+
+    #[inline]
+    fn from_f64(x: f64) -> Self {
+        x as f32
+    }
+
+    #[inline]
+    fn as_f32(self) -> f32 {
+        self
+    }
+
+    #[inline]
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Sample for f64 {
+    #[inline]
+    fn from_f32(x: f32) -> Self {
+        x as f64
+    }
+
+    #[inline]
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    #[inline]
+    fn as_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline]
+    fn as_f64(self) -> f64 {
+        self
+    }
+}
+
+/// State below this magnitude is flushed to zero, long before it would
+/// decay into the slow denormal range
+const DENORMAL_THRESHOLD: f64 = 1e-20;
+
+/// Samples the output fades back in over after a recovery
+pub const RECOVERY_FADE_SAMPLES: u32 = 256;
+
+/// Flush a state variable to zero once it has decayed below audibility.
+/// Returns false if it is NaN or infinite.
+#[inline]
+pub fn flush_state<T: Sample>(x: &mut T) -> bool {
+    if x.abs() < T::from_f64(DENORMAL_THRESHOLD) {
+        *x = T::zero();
+    }
+    x.is_finite()
+}
+
+/// Bookkeeping for filters that reset themselves after their state went
+/// non-finite: a short fade back in, and a count the plugin can report
+#[derive(Clone, Copy, Default)]
+pub struct Recovery {
+    fade_remaining: u32,
+    count: u32,
+}
+
+impl Recovery {
+    /// Record a reset and start fading back in
+    pub fn trigger(&mut self) {
+        self.count = self.count.wrapping_add(1);
+        self.fade_remaining = RECOVERY_FADE_SAMPLES;
+    }
+
+    /// Number of recoveries so far
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Gain for the next output sample while fading in
+    #[inline]
+    pub fn next_gain(&mut self) -> Option<f32> {
+        if self.fade_remaining == 0 {
+            return None;
+        }
+        self.fade_remaining -= 1;
+        Some(1.0 - self.fade_remaining as f32 / RECOVERY_FADE_SAMPLES as f32)
+    }
+}
+
+/// Filter topology a chain runs its stages through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterEngine {
+    /// Direct Form 2 Transposed biquads
+    #[default]
+    Biquad,
+    /// Zero-delay-feedback state variable filters, for fast modulation
+    Svf,
+}
+
+/// Evaluation of a filter's transfer function on the unit circle, for
+/// plots and tests that need the response without rendering audio
+pub trait FrequencyResponse {
+    /// Complex transfer function at the given frequency
+    fn response(&self, sample_rate: f32, freq: f32) -> Complex64;
+
+    /// Group delay in seconds at the given frequency
+    fn group_delay(&self, sample_rate: f32, freq: f32) -> f32;
+
+    /// Linear magnitude at the given frequency
+    fn magnitude(&self, sample_rate: f32, freq: f32) -> f32 {
+        self.response(sample_rate, freq).norm() as f32
+    }
+
+    /// Magnitude in dB at the given frequency
+    fn magnitude_db(&self, sample_rate: f32, freq: f32) -> f32 {
+        (20.0 * self.response(sample_rate, freq).norm().max(1e-12).log10()) as f32
+    }
+
+    /// Phase in radians, wrapped to -pi..pi, at the given frequency
+    fn phase(&self, sample_rate: f32, freq: f32) -> f32 {
+        self.response(sample_rate, freq).arg() as f32
+    }
+
+    /// Fill `table` with the response at log-spaced frequencies from
+    /// `min_freq` to `max_freq`
+    fn fill_response_table(
+        &self,
+        sample_rate: f32,
+        min_freq: f32,
+        max_freq: f32,
+        table: &mut [ResponsePoint],
+    ) {
+        let steps = table.len().saturating_sub(1).max(1) as f32;
+        let ratio = max_freq / min_freq;
+        for (i, point) in table.iter_mut().enumerate() {
+            let freq = min_freq * ratio.powf(i as f32 / steps);
+            let response = self.response(sample_rate, freq);
+            *point = ResponsePoint {
+                freq,
+                magnitude_db: (20.0 * response.norm().max(1e-12).log10()) as f32,
+                phase: response.arg() as f32,
+                group_delay: self.group_delay(sample_rate, freq),
+            };
+        }
+    }
+}
+
+/// One row of a response table
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResponsePoint {
+    pub freq: f32,
+    pub magnitude_db: f32,
+    /// Phase in radians
+    pub phase: f32,
+    /// Group delay in seconds
+    pub group_delay: f32,
+}
+
+/// Normalized angular frequency in radians per sample
+pub(crate) fn angular_frequency(sample_rate: f32, freq: f32) -> f64 {
+    2.0 * std::f64::consts::PI * freq as f64 / sample_rate as f64
+}
+
+/// Evaluate the polynomial `sum(c[k] z^-k)` at `z = e^jw`, along with the
+/// sum weighted by `k` that its group delay needs
+fn evaluate_polynomial(coefficients: &[f64], w: f64) -> (Complex64, Complex64) {
+    let mut value = Complex64::default();
+    let mut weighted = Complex64::default();
+    for (k, &c) in coefficients.iter().enumerate() {
+        let term = Complex64::from_polar(c, -(k as f64) * w);
+        value += term;
+        weighted += term * k as f64;
+    }
+    (value, weighted)
+}
+
+/// Transfer function and group delay in samples of `b(z) / a(z)`
+pub(crate) fn section_response(b: &[f64], a: &[f64], w: f64) -> (Complex64, f64) {
+    let (num, num_weighted) = evaluate_polynomial(b, w);
+    let (den, den_weighted) = evaluate_polynomial(a, w);
+    // A polynomial's group delay is Re(sum(k c[k] z^-k) / sum(c[k] z^-k))
+    let delay = |value: Complex64, weighted: Complex64| {
+        if value.norm_sqr() > 0.0 {
+            (weighted / value).re
+        } else {
+            0.0
+        }
+    };
+    (
+        num / den,
+        delay(num, num_weighted) - delay(den, den_weighted),
+    )
+}
+
+/// Biquad filter section using Direct Form 2 Transposed
+#[derive(Clone, Copy, Default)]
+pub struct BiquadState<T = f32> {
+    // Normalized coefficients
+    b0: T,
+    b1: T,
+    b2: T,
+    a1: T,
+    a2: T,
+    /// Coefficients being ramped to, and the change per sample
+    target: [T; 5],
+    step: [T; 5],
+    /// Samples left until the coefficients land on the target
+    remaining: u32,
+    // Filter state
+    z1: T,
+    z2: T,
+}
+
+impl<T: Sample> BiquadState<T> {
+    /// Calculate coefficients for the given response type
+    pub fn set(
+        &mut self,
+        filter_type: FilterType,
+        sample_rate: f32,
+        freq: f32,
+        q: f32,
+        gain_db: f32,
+    ) {
+        match filter_type {
+            FilterType::HighPass => self.set_highpass(sample_rate, freq, q),
+            FilterType::LowPass => self.set_lowpass(sample_rate, freq, q),
+            FilterType::BandPass => self.set_bandpass(sample_rate, freq, q),
+            FilterType::Notch => self.set_notch(sample_rate, freq, q),
+            FilterType::Peak => self.set_peaking(sample_rate, freq, q, gain_db),
+            FilterType::LowShelf => self.set_lowshelf(sample_rate, freq, q, gain_db),
+            FilterType::HighShelf => self.set_highshelf(sample_rate, freq, q, gain_db),
+            FilterType::AllPass => self.set_allpass(sample_rate, freq, q),
+        }
+    }
+
+    /// Calculate high-pass filter coefficients using RBJ cookbook
+    pub fn set_highpass(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w0, alpha) = rbj_intermediates::<T>(sample_rate, freq, q);
+        let (one, two) = (T::one(), T::from_f64(2.0));
+
+        let b0 = (one + cos_w0) / two;
+        let b1 = -(one + cos_w0);
+        let b2 = (one + cos_w0) / two;
+        let a0 = one + alpha;
+        let a1 = -two * cos_w0;
+        let a2 = one - alpha;
+
+        self.set_normalized(b0, b1, b2, a0, a1, a2);
+    }
+
+    /// Calculate low-pass filter coefficients using RBJ cookbook
+    pub fn set_lowpass(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w0, alpha) = rbj_intermediates::<T>(sample_rate, freq, q);
+        let (one, two) = (T::one(), T::from_f64(2.0));
+
+        let b0 = (one - cos_w0) / two;
+        let b1 = one - cos_w0;
+        let b2 = (one - cos_w0) / two;
+        let a0 = one + alpha;
+        let a1 = -two * cos_w0;
+        let a2 = one - alpha;
+
+        self.set_normalized(b0, b1, b2, a0, a1, a2);
+    }
+
+    /// Calculate band-pass coefficients (constant 0 dB peak gain) using RBJ cookbook
+    pub fn set_bandpass(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w0, alpha) = rbj_intermediates::<T>(sample_rate, freq, q);
+        let (one, two) = (T::one(), T::from_f64(2.0));
+
+        let b0 = alpha;
+        let b1 = T::zero();
+        let b2 = -alpha;
+        let a0 = one + alpha;
+        let a1 = -two * cos_w0;
+        let a2 = one - alpha;
+
+        self.set_normalized(b0, b1, b2, a0, a1, a2);
+    }
+
+    /// Calculate notch filter coefficients using RBJ cookbook
+    pub fn set_notch(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w0, alpha) = rbj_intermediates::<T>(sample_rate, freq, q);
+        let (one, two) = (T::one(), T::from_f64(2.0));
+
+        let b0 = one;
+        let b1 = -two * cos_w0;
+        let b2 = one;
+        let a0 = one + alpha;
+        let a1 = -two * cos_w0;
+        let a2 = one - alpha;
+
+        self.set_normalized(b0, b1, b2, a0, a1, a2);
+    }
+
+    /// Calculate peaking EQ coefficients using RBJ cookbook
+    pub fn set_peaking(&mut self, sample_rate: f32, freq: f32, q: f32, gain_db: f32) {
+        let (cos_w0, alpha) = rbj_intermediates::<T>(sample_rate, freq, q);
+        let (one, two) = (T::one(), T::from_f64(2.0));
+        let a = T::from_f64(10.0f64.powf(gain_db as f64 / 40.0));
+
+        let b0 = one + alpha * a;
+        let b1 = -two * cos_w0;
+        let b2 = one - alpha * a;
+        let a0 = one + alpha / a;
+        let a1 = -two * cos_w0;
+        let a2 = one - alpha / a;
+
+        self.set_normalized(b0, b1, b2, a0, a1, a2);
+    }
+
+    /// Calculate low-shelf coefficients using RBJ cookbook
+    pub fn set_lowshelf(&mut self, sample_rate: f32, freq: f32, q: f32, gain_db: f32) {
+        let (cos_w0, alpha) = rbj_intermediates::<T>(sample_rate, freq, q);
+        let (one, two) = (T::one(), T::from_f64(2.0));
+        let a = T::from_f64(10.0f64.powf(gain_db as f64 / 40.0));
+        let two_sqrt_a_alpha = two * a.sqrt() * alpha;
+
+        let b0 = a * ((a + one) - (a - one) * cos_w0 + two_sqrt_a_alpha);
+        let b1 = two * a * ((a - one) - (a + one) * cos_w0);
+        let b2 = a * ((a + one) - (a - one) * cos_w0 - two_sqrt_a_alpha);
+        let a0 = (a + one) + (a - one) * cos_w0 + two_sqrt_a_alpha;
+        let a1 = -two * ((a - one) + (a + one) * cos_w0);
+        let a2 = (a + one) + (a - one) * cos_w0 - two_sqrt_a_alpha;
+
+        self.set_normalized(b0, b1, b2, a0, a1, a2);
+    }
+
+    /// Calculate high-shelf coefficients using RBJ cookbook
+    pub fn set_highshelf(&mut self, sample_rate: f32, freq: f32, q: f32, gain_db: f32) {
+        let (cos_w0, alpha) = rbj_intermediates::<T>(sample_rate, freq, q);
+        let (one, two) = (T::one(), T::from_f64(2.0));
+        let a = T::from_f64(10.0f64.powf(gain_db as f64 / 40.0));
+        let two_sqrt_a_alpha = two * a.sqrt() * alpha;
+
+        let b0 = a * ((a + one) + (a - one) * cos_w0 + two_sqrt_a_alpha);
+        let b1 = -two * a * ((a - one) + (a + one) * cos_w0);
+        let b2 = a * ((a + one) + (a - one) * cos_w0 - two_sqrt_a_alpha);
+        let a0 = (a + one) - (a - one) * cos_w0 + two_sqrt_a_alpha;
+        let a1 = two * ((a - one) - (a + one) * cos_w0);
+        let a2 = (a + one) - (a - one) * cos_w0 - two_sqrt_a_alpha;
+
+        self.set_normalized(b0, b1, b2, a0, a1, a2);
+    }
+
+    /// Calculate all-pass coefficients using RBJ cookbook
+    pub fn set_allpass(&mut self, sample_rate: f32, freq: f32, q: f32) {
+        let (cos_w0, alpha) = rbj_intermediates::<T>(sample_rate, freq, q);
+        let (one, two) = (T::one(), T::from_f64(2.0));
+
+        let b0 = one - alpha;
+        let b1 = -two * cos_w0;
+        let b2 = one + alpha;
+        let a0 = one + alpha;
+        let a1 = -two * cos_w0;
+        let a2 = one - alpha;
+
+        self.set_normalized(b0, b1, b2, a0, a1, a2);
+    }
+
+    /// Store coefficients normalized by a0
+    fn set_normalized(&mut self, b0: T, b1: T, b2: T, a0: T, a1: T, a2: T) {
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = a1 / a0;
+        self.a2 = a2 / a0;
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process(&mut self, input: T) -> T {
+        self.advance_ramp();
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+
+    /// Process a block of samples in place
+    pub fn process_block(&mut self, samples: &mut [T]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Copy coefficients from another section, keeping this section's state
+    pub fn copy_coefficients(&mut self, other: &Self) {
+        self.ramp_coefficients(other, 0);
+    }
+
+    /// Move linearly to the coefficients of another section over the next
+    /// `samples` samples, or at once for 0. Stable sections stay stable
+    /// along the way, as the stable region of `a1` and `a2` is convex.
+    pub fn ramp_coefficients(&mut self, other: &Self, samples: u32) {
+        self.target = [other.b0, other.b1, other.b2, other.a1, other.a2];
+        self.remaining = samples;
+        if samples == 0 {
+            [self.b0, self.b1, self.b2, self.a1, self.a2] = self.target;
+        } else {
+            let current = [self.b0, self.b1, self.b2, self.a1, self.a2];
+            self.step = ramp_steps(current, self.target, samples);
+        }
+    }
+
+    /// Take one step of a coefficient ramp
+    #[inline]
+    fn advance_ramp(&mut self) {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            if self.remaining == 0 {
+                [self.b0, self.b1, self.b2, self.a1, self.a2] = self.target;
+            } else {
+                let [b0, b1, b2, a1, a2] = self.step;
+                self.b0 += b0;
+                self.b1 += b1;
+                self.b2 += b2;
+                self.a1 += a1;
+                self.a2 += a2;
+            }
+        }
+    }
+
+    /// Normalized coefficients as [b0, b1, b2, a1, a2]
+    pub fn coefficients(&self) -> [T; 5] {
+        [self.b0, self.b1, self.b2, self.a1, self.a2]
+    }
+
+    /// Natural frequency of the section's poles
+    pub fn pole_frequency(&self, sample_rate: f32) -> f32 {
+        // Pre-warped g from the bilinear denominator 1 + k g + g^2
+        let one = T::one();
+        let g = ((one + self.a1 + self.a2) / (one - self.a1 + self.a2)).sqrt();
+        (g.atan() / T::PI()).as_f32() * sample_rate
+    }
+
+    /// Flush denormal state to zero. Returns false if the state is no
+    /// longer finite.
+    pub fn sanitize_state(&mut self) -> bool {
+        flush_state(&mut self.z1) & flush_state(&mut self.z2)
+    }
+
+    /// Reset filter state
+    pub fn reset(&mut self) {
+        self.z1 = T::zero();
+        self.z2 = T::zero();
+    }
+}
+
+/// Shared RBJ cookbook intermediates: (cos(w0), alpha)
+fn rbj_intermediates<T: Sample>(sample_rate: f32, freq: f32, q: f32) -> (T, T) {
+    let two = T::from_f64(2.0);
+    let w0 = two * T::PI() * T::from_f32(freq) / T::from_f32(sample_rate);
+    (w0.cos(), w0.sin() / (two * T::from_f32(q)))
+}
+
+/// Per-sample increments moving `current` to `target` over `samples` steps
+pub(crate) fn ramp_steps<T: Sample, const C: usize>(
+    current: [T; C],
+    target: [T; C],
+    samples: u32,
+) -> [T; C] {
+    let scale = T::one() / T::from_f64(samples as f64);
+    std::array::from_fn(|i| (target[i] - current[i]) * scale)
+}
+
+/// Pre-warped analog frequency via bilinear transform, normalized to tan(wc*T/2)
+pub(crate) fn prewarp<T: Sample>(sample_rate: f32, freq: f32) -> T {
+    (T::PI() * T::from_f32(freq) / T::from_f32(sample_rate)).tan()
+}
+
+impl<T: Sample> FrequencyResponse for BiquadState<T> {
+    fn response(&self, sample_rate: f32, freq: f32) -> Complex64 {
+        let [b0, b1, b2, a1, a2] = self.coefficients().map(T::as_f64);
+        let w = angular_frequency(sample_rate, freq);
+        section_response(&[b0, b1, b2], &[1.0, a1, a2], w).0
+    }
+
+    fn group_delay(&self, sample_rate: f32, freq: f32) -> f32 {
+        let [b0, b1, b2, a1, a2] = self.coefficients().map(T::as_f64);
+        let w = angular_frequency(sample_rate, freq);
+        let (_, delay) = section_response(&[b0, b1, b2], &[1.0, a1, a2], w);
+        (delay / sample_rate as f64) as f32
+    }
+}
+
+/// First-order filter section for 6dB and 18dB slopes
+#[derive(Clone, Copy, Default)]
+pub struct FirstOrderHPState<T = f32> {
+    b0: T,
+    b1: T,
+    a1: T,
+    /// Coefficients being ramped to, and the change per sample
+    target: [T; 3],
+    step: [T; 3],
+    /// Samples left until the coefficients land on the target
+    remaining: u32,
+    x1: T,
+    y1: T,
+}
+
+impl<T: Sample> FirstOrderHPState<T> {
+    /// Calculate coefficients for the given response type.
+    /// Types without a first-order form leave the stage transparent.
+    pub fn set(&mut self, filter_type: FilterType, sample_rate: f32, freq: f32, gain_db: f32) {
+        match filter_type {
+            FilterType::HighPass => self.set_highpass(sample_rate, freq),
+            FilterType::LowPass => self.set_lowpass(sample_rate, freq),
+            FilterType::LowShelf => self.set_lowshelf(sample_rate, freq, gain_db),
+            FilterType::HighShelf => self.set_highshelf(sample_rate, freq, gain_db),
+            FilterType::AllPass => self.set_allpass(sample_rate, freq),
+            FilterType::BandPass | FilterType::Notch | FilterType::Peak => {
+                self.b0 = T::one();
+                self.b1 = T::zero();
+                self.a1 = T::zero();
+            }
+        }
+    }
+
+    /// Set first-order high-pass coefficients
+    pub fn set_highpass(&mut self, sample_rate: f32, freq: f32) {
+        let g = prewarp::<T>(sample_rate, freq);
+        let one = T::one();
+
+        self.b0 = one / (one + g);
+        self.b1 = -self.b0;
+        self.a1 = (g - one) / (g + one);
+    }
+
+    /// Set first-order low-pass coefficients
+    pub fn set_lowpass(&mut self, sample_rate: f32, freq: f32) {
+        let g = prewarp::<T>(sample_rate, freq);
+        let one = T::one();
+
+        self.b0 = g / (one + g);
+        self.b1 = self.b0;
+        self.a1 = (g - one) / (g + one);
+    }
+
+    /// Set first-order low-shelf coefficients, H(s) = (s + G*wc) / (s + wc)
+    pub fn set_lowshelf(&mut self, sample_rate: f32, freq: f32, gain_db: f32) {
+        let g = prewarp::<T>(sample_rate, freq);
+        let one = T::one();
+        let gain = T::from_f64(10.0f64.powf(gain_db as f64 / 20.0));
+
+        self.b0 = (one + gain * g) / (one + g);
+        self.b1 = (gain * g - one) / (one + g);
+        self.a1 = (g - one) / (g + one);
+    }
+
+    /// Set first-order high-shelf coefficients, H(s) = (G*s + wc) / (s + wc)
+    pub fn set_highshelf(&mut self, sample_rate: f32, freq: f32, gain_db: f32) {
+        let g = prewarp::<T>(sample_rate, freq);
+        let one = T::one();
+        let gain = T::from_f64(10.0f64.powf(gain_db as f64 / 20.0));
+
+        self.b0 = (gain + g) / (one + g);
+        self.b1 = (g - gain) / (one + g);
+        self.a1 = (g - one) / (g + one);
+    }
+
+    /// Set first-order all-pass coefficients (-90 degrees at the cutoff)
+    pub fn set_allpass(&mut self, sample_rate: f32, freq: f32) {
+        let g = prewarp::<T>(sample_rate, freq);
+        let one = T::one();
+
+        self.a1 = (g - one) / (g + one);
+        self.b0 = self.a1;
+        self.b1 = one;
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process(&mut self, input: T) -> T {
+        self.advance_ramp();
+
+        let output = self.b0 * input + self.b1 * self.x1 - self.a1 * self.y1;
+        self.x1 = input;
+        self.y1 = output;
+        output
+    }
+
+    /// Process a block of samples in place
+    pub fn process_block(&mut self, samples: &mut [T]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Copy coefficients from another stage, keeping this stage's state
+    pub fn copy_coefficients(&mut self, other: &Self) {
+        self.ramp_coefficients(other, 0);
+    }
+
+    /// Move linearly to the coefficients of another stage over the next
+    /// `samples` samples, or at once for 0
+    pub fn ramp_coefficients(&mut self, other: &Self, samples: u32) {
+        self.target = [other.b0, other.b1, other.a1];
+        self.remaining = samples;
+        if samples == 0 {
+            [self.b0, self.b1, self.a1] = self.target;
+        } else {
+            self.step = ramp_steps([self.b0, self.b1, self.a1], self.target, samples);
+        }
+    }
+
+    /// Take one step of a coefficient ramp
+    #[inline]
+    fn advance_ramp(&mut self) {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            if self.remaining == 0 {
+                [self.b0, self.b1, self.a1] = self.target;
+            } else {
+                let [b0, b1, a1] = self.step;
+                self.b0 += b0;
+                self.b1 += b1;
+                self.a1 += a1;
+            }
+        }
+    }
+
+    /// Coefficients as [b0, b1, a1]
+    pub fn coefficients(&self) -> [T; 3] {
+        [self.b0, self.b1, self.a1]
+    }
+
+    /// Frequency of the stage's pole
+    pub fn pole_frequency(&self, sample_rate: f32) -> f32 {
+        // Pre-warped g from the bilinear denominator (g + 1) + (g - 1) z^-1
+        let one = T::one();
+        let g = (one + self.a1) / (one - self.a1);
+        (g.atan() / T::PI()).as_f32() * sample_rate
+    }
+
+    /// Flush denormal state to zero. Returns false if the state is no
+    /// longer finite.
+    pub fn sanitize_state(&mut self) -> bool {
+        flush_state(&mut self.x1) & flush_state(&mut self.y1)
+    }
+
+    /// Reset filter state
+    pub fn reset(&mut self) {
+        self.x1 = T::zero();
+        self.y1 = T::zero();
+    }
+}
+
+impl<T: Sample> FrequencyResponse for FirstOrderHPState<T> {
+    fn response(&self, sample_rate: f32, freq: f32) -> Complex64 {
+        let [b0, b1, a1] = self.coefficients().map(T::as_f64);
+        let w = angular_frequency(sample_rate, freq);
+        section_response(&[b0, b1], &[1.0, a1], w).0
+    }
+
+    fn group_delay(&self, sample_rate: f32, freq: f32) -> f32 {
+        let [b0, b1, a1] = self.coefficients().map(T::as_f64);
+        let w = angular_frequency(sample_rate, freq);
+        let (_, delay) = section_response(&[b0, b1], &[1.0, a1], w);
+        (delay / sample_rate as f64) as f32
+    }
+}
+
+/// Complete filter chain for one channel.
+///
+/// Stages live in a fixed array sized for the steepest slope, so changing
+/// slope never allocates on the audio thread. `T` is the precision of the
+/// coefficient math and filter state.
+#[derive(Clone, Copy, Default)]
+pub struct FilterChain<T = f32> {
+    biquad_stages: [BiquadState<T>; MAX_SECTIONS],
+    first_order: FirstOrderHPState<T>,
+    svf_stages: [SvfState<T>; MAX_SECTIONS],
+    one_pole: OnePoleState<T>,
+    /// Second-order stage count, on whichever engine is active
+    active_biquads: usize,
+    use_first_order: bool,
+    engine: FilterEngine,
+    recovery: Recovery,
+    alignment: FilterAlignment,
+    ripple_db: f32,
+    prototype: Prototype,
+    /// Order the cached prototype was designed for, 0 when stale
+    prototype_order: usize,
+}
+
+impl<T: Sample> FilterChain<T> {
+    /// Select the engine the stages run on. Switching clears the filter
+    /// state; call `update_coefficients` afterwards to configure the new
+    /// engine's stages.
+    pub fn set_engine(&mut self, engine: FilterEngine) {
+        if engine != self.engine {
+            self.engine = engine;
+            self.reset();
+        }
+    }
+
+    /// Engine the stages run on
+    pub fn engine(&self) -> FilterEngine {
+        self.engine
+    }
+
+    /// Select the pole alignment used by the high-pass, low-pass and all-pass
+    /// responses. The ripple only applies to Chebyshev.
+    pub fn set_alignment(&mut self, alignment: FilterAlignment, ripple_db: f32) {
+        if alignment != self.alignment || ripple_db != self.ripple_db {
+            self.alignment = alignment;
+            self.ripple_db = ripple_db;
+            self.prototype_order = 0;
+        }
+    }
+
+    /// Update filter coefficients based on current parameters.
+    ///
+    /// High-pass, low-pass and all-pass place each stage according to the
+    /// alignment, so the nominal cutoff holds for every slope; `q` scales the
+    /// highest-Q stage as extra resonance (0.707 leaves the alignment as-is).
+    ///
+    /// Responses without a first-order form (band-pass, notch, peak) use one
+    /// biquad per 12 dB/oct, rounded up. Gain is split evenly across the
+    /// active stages so cascading only steepens the transition.
+    pub fn update_coefficients(
+        &mut self,
+        sample_rate: f32,
+        freq: f32,
+        q: f32,
+        gain_db: f32,
+        filter_type: FilterType,
+        slope: FilterSlope,
+    ) {
+        if filter_type.uses_alignment() {
+            self.update_aligned(sample_rate, freq, q, filter_type, slope);
+            return;
+        }
+
+        let order = slope.order();
+        let (active_biquads, use_first_order) = if filter_type.has_first_order() {
+            (order / 2, order % 2 == 1)
+        } else {
+            (order.div_ceil(2), false)
+        };
+
+        let num_stages = active_biquads + usize::from(use_first_order);
+        let stage_gain_db = gain_db / num_stages as f32;
+
+        for i in 0..active_biquads {
+            self.set_stage(i, filter_type, sample_rate, freq, q, stage_gain_db);
+        }
+        if use_first_order {
+            self.set_first_order_stage(filter_type, sample_rate, freq, stage_gain_db);
+        }
+
+        self.active_biquads = active_biquads;
+        self.use_first_order = use_first_order;
+    }
+
+    /// Place stages from the cached alignment prototype
+    fn update_aligned(
+        &mut self,
+        sample_rate: f32,
+        freq: f32,
+        q: f32,
+        filter_type: FilterType,
+        slope: FilterSlope,
+    ) {
+        let order = slope.order();
+        if order != self.prototype_order {
+            self.prototype = Prototype::new(self.alignment, order, self.ripple_db);
+            self.prototype_order = order;
+        }
+
+        // Low-pass poles scale up from the cutoff, high-pass (and the
+        // matching all-pass) poles scale down. Scaling happens in the
+        // pre-warped domain so the whole cascade shares one bilinear
+        // transform anchored at the cutoff, instead of each section warping
+        // around its own frequency.
+        let warped = prewarp::<T>(sample_rate, freq);
+        let stage_freq = |pole: f32| {
+            let pole = T::from_f32(pole);
+            let stage_warped = match filter_type {
+                FilterType::LowPass => warped * pole,
+                _ => warped / pole,
+            };
+            (stage_warped.atan() * T::from_f32(sample_rate) / T::PI()).as_f32()
+        };
+
+        let prototype = self.prototype;
+        let sections = prototype.sections();
+        let resonance = q / FRAC_1_SQRT_2;
+        let last = sections.len().saturating_sub(1);
+        for (i, section) in sections.iter().enumerate() {
+            let stage_q = if i == last {
+                section.q * resonance
+            } else {
+                section.q
+            };
+            self.set_stage(
+                i,
+                filter_type,
+                sample_rate,
+                stage_freq(section.freq),
+                stage_q,
+                0.0,
+            );
+        }
+
+        if let Some(pole) = prototype.first_order {
+            self.set_first_order_stage(filter_type, sample_rate, stage_freq(pole), 0.0);
+        }
+
+        self.active_biquads = sections.len();
+        self.use_first_order = prototype.first_order.is_some();
+    }
+
+    /// Configure second-order stage `i` on the active engine
+    fn set_stage(
+        &mut self,
+        i: usize,
+        filter_type: FilterType,
+        sample_rate: f32,
+        freq: f32,
+        q: f32,
+        gain_db: f32,
+    ) {
+        match self.engine {
+            FilterEngine::Biquad => {
+                self.biquad_stages[i].set(filter_type, sample_rate, freq, q, gain_db)
+            }
+            FilterEngine::Svf => self.svf_stages[i].set(filter_type, sample_rate, freq, q, gain_db),
+        }
+    }
+
+    /// Configure the first-order stage on the active engine
+    fn set_first_order_stage(
+        &mut self,
+        filter_type: FilterType,
+        sample_rate: f32,
+        freq: f32,
+        gain_db: f32,
+    ) {
+        match self.engine {
+            FilterEngine::Biquad => self
+                .first_order
+                .set(filter_type, sample_rate, freq, gain_db),
+            FilterEngine::Svf => self.one_pole.set(filter_type, sample_rate, freq, gain_db),
+        }
+    }
+
+    /// Process single sample through the filter chain
+    #[inline]
+    pub fn process(&mut self, mut sample: T) -> T {
+        match self.engine {
+            FilterEngine::Biquad => {
+                if self.use_first_order {
+                    sample = self.first_order.process(sample);
+                }
+                for stage in &mut self.biquad_stages[..self.active_biquads] {
+                    sample = stage.process(sample);
+                }
+            }
+            FilterEngine::Svf => {
+                if self.use_first_order {
+                    sample = self.one_pole.process(sample);
+                }
+                for stage in &mut self.svf_stages[..self.active_biquads] {
+                    sample = stage.process(sample);
+                }
+            }
+        }
+
+        if let Some(gain) = self.recovery.next_gain() {
+            sample *= T::from_f32(gain);
+        }
+        sample
+    }
+
+    /// Process a block of samples in place, one stage at a time, then
+    /// `sanitize` the chain
+    pub fn process_block(&mut self, samples: &mut [T]) {
+        match self.engine {
+            FilterEngine::Biquad => {
+                if self.use_first_order {
+                    self.first_order.process_block(samples);
+                }
+                for stage in &mut self.biquad_stages[..self.active_biquads] {
+                    stage.process_block(samples);
+                }
+            }
+            FilterEngine::Svf => {
+                if self.use_first_order {
+                    self.one_pole.process_block(samples);
+                }
+                for stage in &mut self.svf_stages[..self.active_biquads] {
+                    stage.process_block(samples);
+                }
+            }
+        }
+
+        for sample in samples.iter_mut() {
+            let Some(gain) = self.recovery.next_gain() else {
+                break;
+            };
+            *sample *= T::from_f32(gain);
+        }
+
+        if self.sanitize() {
+            for sample in samples.iter_mut().filter(|sample| !sample.is_finite()) {
+                *sample = T::zero();
+            }
+        }
+    }
+
+    /// Flush denormal state to zero and, if any stage has gone NaN or
+    /// infinite, reset the chain and fade its output back in. Meant to run
+    /// once per block. Returns whether the chain was reset.
+    pub fn sanitize(&mut self) -> bool {
+        // Inactive stages too, since a slope change would bring them back
+        let mut finite = self.first_order.sanitize_state() & self.one_pole.sanitize_state();
+        for stage in &mut self.biquad_stages {
+            finite &= stage.sanitize_state();
+        }
+        for stage in &mut self.svf_stages {
+            finite &= stage.sanitize_state();
+        }
+
+        if !finite {
+            self.reset();
+            self.recovery.trigger();
+        }
+        !finite
+    }
+
+    /// Number of times the chain has reset itself after non-finite state
+    pub fn recoveries(&self) -> u32 {
+        self.recovery.count()
+    }
+
+    /// Active biquad stages, in processing order after the first-order
+    /// stage. Empty on the SVF engine.
+    pub fn biquads(&self) -> &[BiquadState<T>] {
+        match self.engine {
+            FilterEngine::Biquad => &self.biquad_stages[..self.active_biquads],
+            FilterEngine::Svf => &[],
+        }
+    }
+
+    /// Active first-order stage, processed before the biquads
+    pub fn first_order(&self) -> Option<&FirstOrderHPState<T>> {
+        (self.engine == FilterEngine::Biquad && self.use_first_order).then_some(&self.first_order)
+    }
+
+    /// Active SVF stages, in processing order after the one-pole stage.
+    /// Empty on the biquad engine.
+    pub fn svfs(&self) -> &[SvfState<T>] {
+        match self.engine {
+            FilterEngine::Biquad => &[],
+            FilterEngine::Svf => &self.svf_stages[..self.active_biquads],
+        }
+    }
+
+    /// Active one-pole stage of the SVF engine, processed before the SVFs
+    pub fn one_pole(&self) -> Option<&OnePoleState<T>> {
+        (self.engine == FilterEngine::Svf && self.use_first_order).then_some(&self.one_pole)
+    }
+
+    /// Copy the configuration of another chain, keeping this chain's state.
+    /// Cheaper than recomputing identical coefficients per channel.
+    pub fn copy_coefficients(&mut self, other: &Self) {
+        self.ramp_coefficients(other, 0);
+    }
+
+    /// Like `copy_coefficients`, but move the biquad and first-order
+    /// coefficients linearly over the next `samples` samples. A chain with
+    /// a different set of stages is taken at once. The SVF stages always
+    /// take theirs at once, as they follow every sample.
+    pub fn ramp_coefficients(&mut self, other: &Self, samples: u32) {
+        let same_stages = other.engine == self.engine
+            && other.active_biquads == self.active_biquads
+            && other.use_first_order == self.use_first_order;
+        let samples = if same_stages { samples } else { 0 };
+        self.set_engine(other.engine);
+        for (stage, source) in self.biquad_stages.iter_mut().zip(&other.biquad_stages) {
+            stage.ramp_coefficients(source, samples);
+        }
+        self.first_order
+            .ramp_coefficients(&other.first_order, samples);
+        for (stage, source) in self.svf_stages.iter_mut().zip(&other.svf_stages) {
+            stage.copy_coefficients(source);
+        }
+        self.one_pole.copy_coefficients(&other.one_pole);
+        self.active_biquads = other.active_biquads;
+        self.use_first_order = other.use_first_order;
+        self.alignment = other.alignment;
+        self.ripple_db = other.ripple_db;
+        self.prototype = other.prototype;
+        self.prototype_order = other.prototype_order;
+    }
+
+    /// Reset all filter states
+    pub fn reset(&mut self) {
+        self.first_order.reset();
+        for stage in &mut self.biquad_stages {
+            stage.reset();
+        }
+        self.one_pole.reset();
+        for stage in &mut self.svf_stages {
+            stage.reset();
+        }
+    }
+}
+
+impl<T: Sample> FrequencyResponse for FilterChain<T> {
+    /// Product of the active stages' responses
+    fn response(&self, sample_rate: f32, freq: f32) -> Complex64 {
+        let mut response = Complex64::new(1.0, 0.0);
+        if let Some(stage) = self.first_order() {
+            response *= stage.response(sample_rate, freq);
+        }
+        if let Some(stage) = self.one_pole() {
+            response *= stage.response(sample_rate, freq);
+        }
+        for stage in self.biquads() {
+            response *= stage.response(sample_rate, freq);
+        }
+        for stage in self.svfs() {
+            response *= stage.response(sample_rate, freq);
+        }
+
+        response
+    }
+
+    /// Sum of the active stages' group delays
+    fn group_delay(&self, sample_rate: f32, freq: f32) -> f32 {
+        let first_order = self
+            .first_order()
+            .map_or(0.0, |stage| stage.group_delay(sample_rate, freq))
+            + self
+                .one_pole()
+                .map_or(0.0, |stage| stage.group_delay(sample_rate, freq));
+
+        self.biquads()
+            .iter()
+            .map(|stage| stage.group_delay(sample_rate, freq))
+            .chain(
+                self.svfs()
+                    .iter()
+                    .map(|stage| stage.group_delay(sample_rate, freq)),
+            )
+            .sum::<f32>()
+            + first_order
+    }
+}
+
+/// All-pass following the phase of a high-pass or low-pass chain, so a dry
+/// signal run through it blends with the chain's output without combing.
+///
+/// Each stage is matched by a first-order all-pass at its pole frequency.
+/// That is exact for the double poles of Linkwitz-Riley and close for the
+/// other alignments, drifting only for very high-Q stages.
+#[derive(Clone, Copy, Default)]
+pub struct PhaseMatch<T = f32> {
+    stages: [FirstOrderHPState<T>; MAX_SECTIONS],
+    active: usize,
+    /// A high-pass stage ends up half a turn away from its all-pass
+    invert: bool,
+}
+
+impl<T: Sample> PhaseMatch<T> {
+    /// Follow `chain`, designed at `chain_rate` as `filter_type`, with the
+    /// all-passes running at `sample_rate`. Other response types have no
+    /// matching all-pass and leave the dry signal as it is.
+    pub fn follow(
+        &mut self,
+        chain: &FilterChain<T>,
+        chain_rate: f32,
+        sample_rate: f32,
+        filter_type: FilterType,
+    ) {
+        let poles = chain
+            .biquads()
+            .iter()
+            .map(|stage| stage.pole_frequency(chain_rate))
+            .chain(
+                chain
+                    .svfs()
+                    .iter()
+                    .map(|stage| stage.pole_frequency(chain_rate)),
+            )
+            .chain(
+                chain
+                    .first_order()
+                    .map(|stage| stage.pole_frequency(chain_rate)),
+            )
+            .chain(
+                chain
+                    .one_pole()
+                    .map(|stage| stage.pole_frequency(chain_rate)),
+            );
+
+        self.active = 0;
+        if matches!(filter_type, FilterType::HighPass | FilterType::LowPass) {
+            for (stage, freq) in self.stages.iter_mut().zip(poles) {
+                // Oversampled chains can have poles above this Nyquist
+                stage.set_allpass(sample_rate, freq.min(0.49 * sample_rate));
+                self.active += 1;
+            }
+        }
+        self.invert = filter_type == FilterType::HighPass && self.active % 2 == 1;
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process(&mut self, mut sample: T) -> T {
+        for stage in &mut self.stages[..self.active] {
+            sample = stage.process(sample);
+        }
+        if self.invert {
+            -sample
+        } else {
+            sample
+        }
+    }
+
+    /// Active all-pass stages
+    pub fn stages(&self) -> &[FirstOrderHPState<T>] {
+        &self.stages[..self.active]
+    }
+
+    /// Whether the output is polarity inverted
+    pub fn inverted(&self) -> bool {
+        self.invert
+    }
+
+    /// Reset all stage states
+    pub fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.reset();
+        }
+    }
+}
+
+impl<T: Sample> FrequencyResponse for PhaseMatch<T> {
+    fn response(&self, sample_rate: f32, freq: f32) -> Complex64 {
+        let sign = if self.invert { -1.0 } else { 1.0 };
+        self.stages()
+            .iter()
+            .fold(Complex64::new(sign, 0.0), |response, stage| {
+                response * stage.response(sample_rate, freq)
+            })
+    }
+
+    fn group_delay(&self, sample_rate: f32, freq: f32) -> f32 {
+        self.stages()
+            .iter()
+            .map(|stage| stage.group_delay(sample_rate, freq))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_biquad_coefficients() {
+        let mut biquad = BiquadState::<f32>::default();
+        biquad.set_highpass(44100.0, 1000.0, 0.707);
+
+        assert!(biquad.b0.is_finite());
+        assert!(biquad.b1.is_finite());
+        assert!(biquad.b0 > 0.0);
+        assert!(biquad.b1 < 0.0);
+    }
+
+    #[test]
+    fn test_dc_rejection() {
+        let mut filter = FilterChain::default();
+        filter.update_coefficients(
+            44100.0,
+            100.0,
+            0.707,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope12dB,
+        );
+
+        let mut output = 0.0;
+        for _ in 0..10000 {
+            output = filter.process(1.0);
+        }
+        assert!(output.abs() < 0.001, "HPF should reject DC");
+    }
+
+    /// Steady-state peak amplitude of a sine run through the chain
+    fn sine_gain(filter: &mut FilterChain, sample_rate: f32, freq: f32) -> f32 {
+        let mut peak: f32 = 0.0;
+        for n in 0..20000 {
+            let phase = std::f64::consts::TAU * freq as f64 * n as f64 / sample_rate as f64;
+            let x = phase.sin() as f32;
+            let y = filter.process(x);
+            if n >= 10000 {
+                peak = peak.max(y.abs());
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn test_lowpass_passes_dc() {
+        for slope in [FilterSlope::Slope6dB, FilterSlope::Slope18dB] {
+            let mut filter = FilterChain::default();
+            filter.update_coefficients(44100.0, 1000.0, 0.707, 0.0, FilterType::LowPass, slope);
+
+            let mut output = 0.0;
+            for _ in 0..10000 {
+                output = filter.process(1.0);
+            }
+            assert!((output - 1.0).abs() < 0.001, "LPF should pass DC");
+        }
+    }
+
+    #[test]
+    fn test_notch_rejects_center() {
+        let mut filter = FilterChain::default();
+        filter.update_coefficients(
+            44100.0,
+            1000.0,
+            2.0,
+            0.0,
+            FilterType::Notch,
+            FilterSlope::Slope12dB,
+        );
+
+        assert!(sine_gain(&mut filter, 44100.0, 1000.0) < 0.01);
+    }
+
+    #[test]
+    fn test_alignment_cutoff_level() {
+        let cases = [
+            (FilterAlignment::Butterworth, -3.01),
+            (FilterAlignment::LinkwitzRiley, -6.02),
+            (FilterAlignment::Bessel, -3.01),
+            (FilterAlignment::Chebyshev, -3.01),
+        ];
+        for (alignment, expected_db) in cases {
+            for slope in [
+                FilterSlope::Slope18dB,
+                FilterSlope::Slope24dB,
+                FilterSlope::Slope96dB,
+            ] {
+                if alignment == FilterAlignment::LinkwitzRiley && slope == FilterSlope::Slope18dB {
+                    continue;
+                }
+                let mut filter = FilterChain::default();
+                filter.set_alignment(alignment, 0.5);
+                filter.update_coefficients(
+                    44100.0,
+                    1000.0,
+                    FRAC_1_SQRT_2,
+                    0.0,
+                    FilterType::HighPass,
+                    slope,
+                );
+
+                let gain_db = 20.0 * sine_gain(&mut filter, 44100.0, 1000.0).log10();
+                assert!(
+                    (gain_db - expected_db).abs() < 0.3,
+                    "{:?} {:?}: {} dB at cutoff, expected {} dB",
+                    alignment,
+                    slope,
+                    gain_db,
+                    expected_db
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_copied_coefficients_match() {
+        let mut source = FilterChain::default();
+        source.update_coefficients(
+            44100.0,
+            300.0,
+            2.0,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope18dB,
+        );
+        let mut copy = FilterChain::default();
+        copy.copy_coefficients(&source);
+
+        for n in 0..1000 {
+            let input = ((n * 7919) % 200) as f32 / 100.0 - 1.0;
+            assert_eq!(source.process(input), copy.process(input));
+        }
+    }
+
+    #[test]
+    fn test_coefficient_ramp_moves_in_small_steps() {
+        let design = |cutoff: f32| {
+            let mut chain = FilterChain::default();
+            chain.update_coefficients(
+                48000.0,
+                cutoff,
+                0.707,
+                0.0,
+                FilterType::HighPass,
+                FilterSlope::Slope18dB,
+            );
+            chain
+        };
+        let (from, to) = (design(100.0), design(2000.0));
+        let mut chain = FilterChain::default();
+        chain.copy_coefficients(&from);
+        chain.ramp_coefficients(&to, 16);
+
+        let coefficients = |chain: &FilterChain| {
+            let (stage, first) = (&chain.biquad_stages[0], &chain.first_order);
+            [
+                stage.b0, stage.b1, stage.b2, stage.a1, stage.a2, first.b0, first.b1, first.a1,
+            ]
+        };
+        let start = coefficients(&chain);
+        let target = coefficients(&to);
+        let mut previous = start;
+        for _ in 0..16 {
+            chain.process(0.0);
+            let current = coefficients(&chain);
+            // No step is bigger than an even share of the whole move
+            for ((c, p), (s, t)) in current.iter().zip(&previous).zip(start.iter().zip(&target)) {
+                assert!((c - p).abs() <= (t - s).abs() / 16.0 + 1e-6);
+            }
+            previous = current;
+        }
+        assert_eq!(previous, target);
+    }
+
+    #[test]
+    fn test_block_matches_per_sample() {
+        let mut per_sample = FilterChain::default();
+        per_sample.update_coefficients(
+            44100.0,
+            120.0,
+            1.5,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope36dB,
+        );
+        let mut block = per_sample;
+
+        let input: Vec<f32> = (0..512)
+            .map(|n| ((n * 7919) % 200) as f32 / 100.0 - 1.0)
+            .collect();
+        let mut output = input.clone();
+        block.process_block(&mut output);
+
+        for (&x, &y) in input.iter().zip(&output) {
+            assert_eq!(per_sample.process(x), y);
+        }
+    }
+
+    #[test]
+    fn test_steep_slopes() {
+        let cases = [
+            (FilterSlope::Slope36dB, -36.0),
+            (FilterSlope::Slope48dB, -48.0),
+            (FilterSlope::Slope72dB, -72.0),
+            (FilterSlope::Slope96dB, -96.0),
+        ];
+        for (slope, expected_db) in cases {
+            let mut filter = FilterChain::default();
+            filter.update_coefficients(
+                44100.0,
+                1000.0,
+                FRAC_1_SQRT_2,
+                0.0,
+                FilterType::HighPass,
+                slope,
+            );
+
+            let gain_db = 20.0 * sine_gain(&mut filter, 44100.0, 500.0).log10();
+            assert!(
+                (gain_db - expected_db).abs() < 1.5,
+                "{:?}: {} dB one octave below cutoff",
+                slope,
+                gain_db
+            );
+        }
+    }
+
+    #[test]
+    fn test_gain_types_at_center() {
+        let cases = [
+            (FilterType::Peak, 1000.0, 12.0),
+            (FilterType::LowShelf, 50.0, 12.0),
+            (FilterType::HighShelf, 15000.0, -12.0),
+        ];
+        for (filter_type, probe, expected_db) in cases {
+            for slope in [FilterSlope::Slope6dB, FilterSlope::Slope24dB] {
+                let mut filter = FilterChain::default();
+                filter.update_coefficients(44100.0, 1000.0, 0.707, expected_db, filter_type, slope);
+
+                let gain_db = 20.0 * sine_gain(&mut filter, 44100.0, probe).log10();
+                assert!(
+                    (gain_db - expected_db).abs() < 1.0,
+                    "{:?} {:?}: {} dB, expected {} dB",
+                    filter_type,
+                    slope,
+                    gain_db,
+                    expected_db
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_svf_engine_matches_biquad_engine() {
+        for slope in [FilterSlope::Slope18dB, FilterSlope::Slope48dB] {
+            let mut chains = [FilterEngine::Biquad, FilterEngine::Svf].map(|engine| {
+                let mut chain = FilterChain::default();
+                chain.set_engine(engine);
+                chain.set_alignment(FilterAlignment::Bessel, 0.0);
+                chain.update_coefficients(48000.0, 400.0, 1.5, 0.0, FilterType::HighPass, slope);
+                chain
+            });
+            let [biquad, svf] = &mut chains;
+            assert_eq!(svf.biquads().len(), 0);
+            assert_eq!(svf.svfs().len(), biquad.biquads().len());
+
+            for freq in [100.0, 400.0, 3000.0] {
+                let error = (svf.response(48000.0, freq) - biquad.response(48000.0, freq)).norm();
+                assert!(error < 1e-4, "{:?} at {} Hz: {}", slope, freq, error);
+            }
+
+            let mut max_error = 0.0f32;
+            for n in 0..9600 {
+                let x = (2.0 * PI * 700.0 * n as f32 / 48000.0).sin();
+                let error = (svf.process(x) - biquad.process(x)).abs();
+                if n > 4800 {
+                    max_error = max_error.max(error);
+                }
+            }
+            assert!(max_error < 1e-3, "{:?}: error {}", slope, max_error);
+        }
+    }
+
+    /// Analog frequency, relative to the cutoff, that the bilinear transform
+    /// maps `freq` to
+    fn warped(sample_rate: f32, cutoff: f32, freq: f32) -> f64 {
+        let fs = sample_rate as f64;
+        (std::f64::consts::PI * freq as f64 / fs).tan()
+            / (std::f64::consts::PI * cutoff as f64 / fs).tan()
+    }
+
+    #[test]
+    fn test_response_matches_butterworth() {
+        let (sample_rate, cutoff) = (48000.0, 1000.0);
+        for (slope, order) in [
+            (FilterSlope::Slope6dB, 1),
+            (FilterSlope::Slope12dB, 2),
+            (FilterSlope::Slope24dB, 4),
+        ] {
+            let mut filter = FilterChain::<f32>::default();
+            filter.update_coefficients(
+                sample_rate,
+                cutoff,
+                FRAC_1_SQRT_2,
+                0.0,
+                FilterType::HighPass,
+                slope,
+            );
+
+            for freq in [50.0, 500.0, 1000.0, 2000.0, 10000.0] {
+                let omega = warped(sample_rate, cutoff, freq);
+                let expected_db = -10.0 * (1.0 + omega.powi(-2 * order)).log10();
+                let db = filter.magnitude_db(sample_rate, freq) as f64;
+                assert!(
+                    (db - expected_db).abs() < 0.01,
+                    "{:?} at {} Hz: {} dB, expected {} dB",
+                    slope,
+                    freq,
+                    db,
+                    expected_db
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_phase_and_group_delay_match_butterworth() {
+        let (sample_rate, cutoff) = (48000.0, 1000.0);
+        let mut biquad = BiquadState::<f32>::default();
+        biquad.set_highpass(sample_rate, cutoff, FRAC_1_SQRT_2);
+
+        let fs = sample_rate as f64;
+        let wc = 2.0 * std::f64::consts::PI * cutoff as f64 / fs;
+        for freq in [100.0, 700.0, 1000.0, 3000.0, 12000.0] {
+            let omega = warped(sample_rate, cutoff, freq);
+
+            // Analog s^2 / (s^2 + sqrt(2) s + 1), sampled on the warped axis
+            let expected_phase = std::f64::consts::PI
+                - (std::f64::consts::SQRT_2 * omega).atan2(1.0 - omega * omega);
+            let phase = biquad.phase(sample_rate, freq) as f64;
+            assert!(
+                (phase - expected_phase).abs() < 1e-3,
+                "{} Hz: phase {}, expected {}",
+                freq,
+                phase,
+                expected_phase
+            );
+
+            // Analog group delay, scaled by d(omega)/dw of the warping
+            let w = 2.0 * std::f64::consts::PI * freq as f64 / fs;
+            let analog_delay =
+                std::f64::consts::SQRT_2 * (1.0 + omega * omega) / (1.0 + omega.powi(4));
+            let warp_slope = 0.5 / (w / 2.0).cos().powi(2) / (wc / 2.0).tan();
+            let expected_delay = analog_delay * warp_slope / fs;
+            let delay = biquad.group_delay(sample_rate, freq) as f64;
+            assert!(
+                (delay - expected_delay).abs() < expected_delay * 1e-3 + 1e-9,
+                "{} Hz: group delay {}, expected {}",
+                freq,
+                delay,
+                expected_delay
+            );
+        }
+
+        // A chain of identical stages adds up their delays
+        let mut chain = FilterChain::<f32>::default();
+        chain.update_coefficients(
+            sample_rate,
+            cutoff,
+            FRAC_1_SQRT_2,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope12dB,
+        );
+        assert!(
+            (chain.group_delay(sample_rate, 500.0) - biquad.group_delay(sample_rate, 500.0)).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_response_table_is_log_spaced() {
+        let mut filter = FilterChain::<f32>::default();
+        filter.update_coefficients(
+            48000.0,
+            200.0,
+            FRAC_1_SQRT_2,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope24dB,
+        );
+
+        let mut table = [ResponsePoint::default(); 31];
+        filter.fill_response_table(48000.0, 20.0, 20000.0, &mut table);
+
+        assert!((table[0].freq - 20.0).abs() < 1e-3);
+        assert!((table[30].freq - 20000.0).abs() < 0.1);
+        // Three decades over 30 steps
+        for pair in table.windows(2) {
+            assert!((pair[1].freq / pair[0].freq - 10f32.powf(0.1)).abs() < 1e-4);
+        }
+        for point in &table {
+            assert_eq!(point.magnitude_db, filter.magnitude_db(48000.0, point.freq));
+            assert_eq!(point.group_delay, filter.group_delay(48000.0, point.freq));
+        }
+    }
+
+    #[test]
+    fn test_nan_recovery_fades_back_in() {
+        for engine in [FilterEngine::Biquad, FilterEngine::Svf] {
+            let mut filter = FilterChain::default();
+            filter.set_engine(engine);
+            filter.update_coefficients(
+                48000.0,
+                100.0,
+                FRAC_1_SQRT_2,
+                0.0,
+                FilterType::HighPass,
+                FilterSlope::Slope36dB,
+            );
+            let mut fresh = filter;
+
+            let mut block = [0.25; 64];
+            block[20] = f32::NAN;
+            filter.process_block(&mut block);
+            assert_eq!(filter.recoveries(), 1, "{:?}", engine);
+            assert!(block.iter().all(|x| x.is_finite()));
+
+            // Ramps up from silence, then tracks a chain that never saw the NaN
+            let input: Vec<f32> = (0..1024)
+                .map(|n| (2.0 * PI * 1000.0 * n as f32 / 48000.0).sin())
+                .collect();
+            let mut output = input.clone();
+            filter.process_block(&mut output);
+            let mut expected = input.clone();
+            fresh.process_block(&mut expected);
+
+            assert!(output[0].abs() < 0.01);
+            let settled = RECOVERY_FADE_SAMPLES as usize;
+            for (y, e) in output[settled..].iter().zip(&expected[settled..]) {
+                assert!((y - e).abs() < 1e-6, "{:?}: {} vs {}", engine, y, e);
+            }
+            assert_eq!(filter.recoveries(), 1);
+        }
+    }
+
+    #[test]
+    fn test_long_silence_flushes_denormals() {
+        for engine in [FilterEngine::Biquad, FilterEngine::Svf] {
+            let mut filter = FilterChain::default();
+            filter.set_engine(engine);
+            filter.update_coefficients(
+                48000.0,
+                30.0,
+                4.0,
+                0.0,
+                FilterType::HighPass,
+                FilterSlope::Slope18dB,
+            );
+
+            let mut block = [0.0; 512];
+            block[0] = 1.0;
+            // Ten seconds of silence after the impulse
+            for _ in 0..940 {
+                filter.process_block(&mut block);
+                block.fill(0.0);
+            }
+
+            let state_is_zero = |stage: &[f32]| stage.iter().all(|&x| x == 0.0);
+            for stage in filter.biquad_stages {
+                assert!(state_is_zero(&[stage.z1, stage.z2]), "{:?}", engine);
+            }
+            assert!(state_is_zero(&[
+                filter.first_order.x1,
+                filter.first_order.y1
+            ]));
+            for stage in filter.svf_stages {
+                assert!(state_is_zero(&stage.state()), "{:?}", engine);
+            }
+            assert!(state_is_zero(&[filter.one_pole.state()]), "{:?}", engine);
+            filter.process_block(&mut block);
+            assert!(block.iter().all(|&x| x == 0.0));
+            assert_eq!(filter.recoveries(), 0);
+        }
+    }
+
+    /// 24 dB high-pass at 20 Hz and 192 kHz, the worst case for f32
+    fn low_cutoff_chain<T: Sample>() -> FilterChain<T> {
+        let mut filter = FilterChain::<T>::default();
+        filter.update_coefficients(
+            192000.0,
+            20.0,
+            FRAC_1_SQRT_2,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope24dB,
+        );
+        filter
+    }
+
+    #[test]
+    fn test_f64_keeps_low_cutoff_accurate() {
+        let error_db = |magnitude_db: f32| (magnitude_db + 3.0103).abs();
+        let single = error_db(low_cutoff_chain::<f32>().magnitude_db(192000.0, 20.0));
+        let double = error_db(low_cutoff_chain::<f64>().magnitude_db(192000.0, 20.0));
+
+        assert!(double < 1e-3, "f64 cutoff off by {} dB", double);
+        assert!(
+            double * 10.0 < single,
+            "f64 {} dB vs f32 {} dB",
+            double,
+            single
+        );
+    }
+
+    #[test]
+    fn test_f64_lowers_low_cutoff_noise() {
+        /// RMS error in dB of a passband sine against the chain's own response
+        fn residual_db<T: Sample>() -> f64 {
+            let sample_rate = 192000.0;
+            let mut filter = low_cutoff_chain::<T>();
+            let response = filter.response(sample_rate, 1000.0);
+            let w = angular_frequency(sample_rate, 1000.0);
+
+            let mut error = 0.0;
+            for n in 0..2 * 192000 {
+                let x = 0.5 * (w * n as f64).sin();
+                let y = filter.process(T::from_f64(x)).as_f64();
+                // One second for the 20 Hz poles to settle
+                if n >= 192000 {
+                    let expected = 0.5 * response.norm() * (w * n as f64 + response.arg()).sin();
+                    error += (y - expected).powi(2);
+                }
+            }
+            10.0 * (error / 192000.0).log10()
+        }
+
+        let single = residual_db::<f32>();
+        let double = residual_db::<f64>();
+        assert!(double < -150.0, "f64 residual at {} dB", double);
+        assert!(
+            double < single - 60.0,
+            "f64 {} dB vs f32 {} dB",
+            double,
+            single
+        );
+    }
+
+    #[test]
+    fn test_phase_matched_blend_has_no_notch() {
+        let sample_rate = 48000.0;
+        // Lowest level of an even blend across the audio band
+        let worst_blend_db = |wet: &FilterChain, dry: &dyn Fn(f32) -> Complex64| {
+            (0..200)
+                .map(|i| {
+                    let freq = 20.0 * 1000.0f32.powf(i as f32 / 200.0);
+                    let blend = 0.5 * (wet.response(sample_rate, freq) + dry(freq));
+                    20.0 * blend.norm().log10()
+                })
+                .fold(f64::INFINITY, f64::min)
+        };
+        // Most level an even blend loses to phase, against adding magnitudes
+        let worst_phase_loss_db = |wet: &FilterChain, dry: &PhaseMatch| {
+            (0..200)
+                .map(|i| {
+                    let freq = 20.0 * 1000.0f32.powf(i as f32 / 200.0);
+                    let (w, d) = (
+                        wet.response(sample_rate, freq),
+                        dry.response(sample_rate, freq),
+                    );
+                    20.0 * ((w + d).norm() / (w.norm() + d.norm())).log10()
+                })
+                .fold(f64::INFINITY, f64::min)
+        };
+
+        for filter_type in [FilterType::HighPass, FilterType::LowPass] {
+            for alignment in [
+                FilterAlignment::Butterworth,
+                FilterAlignment::LinkwitzRiley,
+                FilterAlignment::Bessel,
+            ] {
+                for slope in [
+                    FilterSlope::Slope12dB,
+                    FilterSlope::Slope18dB,
+                    FilterSlope::Slope24dB,
+                    FilterSlope::Slope48dB,
+                ] {
+                    let mut wet = FilterChain::default();
+                    wet.set_alignment(alignment, 0.0);
+                    wet.update_coefficients(
+                        sample_rate,
+                        1000.0,
+                        FRAC_1_SQRT_2,
+                        0.0,
+                        filter_type,
+                        slope,
+                    );
+                    let mut dry = PhaseMatch::default();
+                    dry.follow(&wet, sample_rate, sample_rate, filter_type);
+
+                    // Half of the dry level is the floor where the wet is silent
+                    let matched = worst_blend_db(&wet, &|freq| dry.response(sample_rate, freq));
+                    assert!(
+                        matched > -6.1,
+                        "{:?} {:?} {:?}: {} dB",
+                        filter_type,
+                        alignment,
+                        slope,
+                        matched
+                    );
+                }
+            }
+        }
+
+        // An odd slope's first-order stage needs its own all-pass, on
+        // either engine, or the blend loses over a dB around the cutoff
+        for engine in [FilterEngine::Biquad, FilterEngine::Svf] {
+            for filter_type in [FilterType::HighPass, FilterType::LowPass] {
+                for alignment in [FilterAlignment::Butterworth, FilterAlignment::Bessel] {
+                    let mut wet = FilterChain::default();
+                    wet.set_engine(engine);
+                    wet.set_alignment(alignment, 0.0);
+                    wet.update_coefficients(
+                        sample_rate,
+                        1000.0,
+                        FRAC_1_SQRT_2,
+                        0.0,
+                        filter_type,
+                        FilterSlope::Slope18dB,
+                    );
+                    let mut dry = PhaseMatch::default();
+                    dry.follow(&wet, sample_rate, sample_rate, filter_type);
+
+                    let loss = worst_phase_loss_db(&wet, &dry);
+                    assert!(
+                        loss > -1.0,
+                        "{:?} {:?} {:?}: {} dB",
+                        engine,
+                        filter_type,
+                        alignment,
+                        loss
+                    );
+                }
+            }
+        }
+
+        // Without the all-pass a 24 dB Butterworth blend notches out
+        let mut wet = FilterChain::default();
+        wet.update_coefficients(
+            sample_rate,
+            1000.0,
+            FRAC_1_SQRT_2,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope24dB,
+        );
+        assert!(worst_blend_db(&wet, &|_| Complex64::new(1.0, 0.0)) < -12.0);
+    }
+}
@@ -0,0 +1,809 @@
+use crate::alignment::MAX_SECTIONS;
+use crate::filter::{
+    flush_state, ramp_steps, BiquadState, FilterChain, FilterEngine, FirstOrderHPState, PhaseMatch,
+    Recovery, Sample,
+};
+use crate::svf::{OnePoleState, SvfState};
+
+/// `flush_state` over every lane. Returns which lanes are still finite.
+fn flush_lanes<T: Sample, const N: usize>(lanes: &mut [T; N]) -> [bool; N] {
+    lanes.each_mut().map(flush_state)
+}
+
+/// Lanes finite in both `a` and `b`
+fn both_finite<const N: usize>(a: [bool; N], b: [bool; N]) -> [bool; N] {
+    std::array::from_fn(|lane| a[lane] && b[lane])
+}
+
+/// Biquad section running `N` channels through shared coefficients.
+/// Each lane is independent, so the per-lane loops compile to SIMD.
+#[derive(Clone, Copy)]
+pub struct BiquadLanes<const N: usize, T = f32> {
+    b0: T,
+    b1: T,
+    b2: T,
+    a1: T,
+    a2: T,
+    /// Coefficients being ramped to, and the change per sample
+    target: [T; 5],
+    step: [T; 5],
+    /// Samples left until the coefficients land on the target
+    remaining: u32,
+    z1: [T; N],
+    z2: [T; N],
+}
+
+impl<const N: usize, T: Sample> Default for BiquadLanes<N, T> {
+    fn default() -> Self {
+        Self {
+            b0: T::zero(),
+            b1: T::zero(),
+            b2: T::zero(),
+            a1: T::zero(),
+            a2: T::zero(),
+            target: [T::zero(); 5],
+            step: [T::zero(); 5],
+            remaining: 0,
+            z1: [T::zero(); N],
+            z2: [T::zero(); N],
+        }
+    }
+}
+
+impl<const N: usize, T: Sample> BiquadLanes<N, T> {
+    /// Take coefficients from a scalar section, keeping lane state
+    pub fn copy_coefficients(&mut self, source: &BiquadState<T>) {
+        self.ramp_coefficients(source, 0);
+    }
+
+    /// Move linearly to the coefficients of a scalar section over the next
+    /// `samples` frames, or at once for 0
+    pub fn ramp_coefficients(&mut self, source: &BiquadState<T>, samples: u32) {
+        self.target = source.coefficients();
+        self.remaining = samples;
+        if samples == 0 {
+            [self.b0, self.b1, self.b2, self.a1, self.a2] = self.target;
+        } else {
+            let current = [self.b0, self.b1, self.b2, self.a1, self.a2];
+            self.step = ramp_steps(current, self.target, samples);
+        }
+    }
+
+    /// Take one step of a coefficient ramp
+    #[inline]
+    fn advance_ramp(&mut self) {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            if self.remaining == 0 {
+                [self.b0, self.b1, self.b2, self.a1, self.a2] = self.target;
+            } else {
+                let [b0, b1, b2, a1, a2] = self.step;
+                self.b0 += b0;
+                self.b1 += b1;
+                self.b2 += b2;
+                self.a1 += a1;
+                self.a2 += a2;
+            }
+        }
+    }
+
+    /// Process one frame, one sample per lane
+    #[inline]
+    pub fn process(&mut self, input: [T; N]) -> [T; N] {
+        self.advance_ramp();
+        let mut output = [T::zero(); N];
+        for (((y, &x), z1), z2) in output
+            .iter_mut()
+            .zip(&input)
+            .zip(&mut self.z1)
+            .zip(&mut self.z2)
+        {
+            *y = self.b0 * x + *z1;
+            *z1 = self.b1 * x - self.a1 * *y + *z2;
+            *z2 = self.b2 * x - self.a2 * *y;
+        }
+        output
+    }
+
+    /// Flush denormal lane state to zero. Returns which lanes are still
+    /// finite.
+    pub fn sanitize_state(&mut self) -> [bool; N] {
+        both_finite(flush_lanes(&mut self.z1), flush_lanes(&mut self.z2))
+    }
+
+    /// Reset the state of one lane
+    pub fn reset_lane(&mut self, lane: usize) {
+        self.z1[lane] = T::zero();
+        self.z2[lane] = T::zero();
+    }
+
+    /// Reset all lane state
+    pub fn reset(&mut self) {
+        self.z1 = [T::zero(); N];
+        self.z2 = [T::zero(); N];
+    }
+}
+
+/// First-order stage running `N` channels through shared coefficients
+#[derive(Clone, Copy)]
+pub struct FirstOrderLanes<const N: usize, T = f32> {
+    b0: T,
+    b1: T,
+    a1: T,
+    /// Coefficients being ramped to, and the change per sample
+    target: [T; 3],
+    step: [T; 3],
+    /// Samples left until the coefficients land on the target
+    remaining: u32,
+    x1: [T; N],
+    y1: [T; N],
+}
+
+impl<const N: usize, T: Sample> Default for FirstOrderLanes<N, T> {
+    fn default() -> Self {
+        Self {
+            b0: T::zero(),
+            b1: T::zero(),
+            a1: T::zero(),
+            target: [T::zero(); 3],
+            step: [T::zero(); 3],
+            remaining: 0,
+            x1: [T::zero(); N],
+            y1: [T::zero(); N],
+        }
+    }
+}
+
+impl<const N: usize, T: Sample> FirstOrderLanes<N, T> {
+    /// Take coefficients from a scalar stage, keeping lane state
+    pub fn copy_coefficients(&mut self, source: &FirstOrderHPState<T>) {
+        self.ramp_coefficients(source, 0);
+    }
+
+    /// Move linearly to the coefficients of a scalar stage over the next
+    /// `samples` frames, or at once for 0
+    pub fn ramp_coefficients(&mut self, source: &FirstOrderHPState<T>, samples: u32) {
+        self.target = source.coefficients();
+        self.remaining = samples;
+        if samples == 0 {
+            [self.b0, self.b1, self.a1] = self.target;
+        } else {
+            self.step = ramp_steps([self.b0, self.b1, self.a1], self.target, samples);
+        }
+    }
+
+    /// Take one step of a coefficient ramp
+    #[inline]
+    fn advance_ramp(&mut self) {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            if self.remaining == 0 {
+                [self.b0, self.b1, self.a1] = self.target;
+            } else {
+                let [b0, b1, a1] = self.step;
+                self.b0 += b0;
+                self.b1 += b1;
+                self.a1 += a1;
+            }
+        }
+    }
+
+    /// Process one frame, one sample per lane
+    #[inline]
+    pub fn process(&mut self, input: [T; N]) -> [T; N] {
+        self.advance_ramp();
+        let mut output = [T::zero(); N];
+        for (((y, &x), x1), y1) in output
+            .iter_mut()
+            .zip(&input)
+            .zip(&mut self.x1)
+            .zip(&mut self.y1)
+        {
+            *y = self.b0 * x + self.b1 * *x1 - self.a1 * *y1;
+            *x1 = x;
+            *y1 = *y;
+        }
+        output
+    }
+
+    /// Flush denormal lane state to zero. Returns which lanes are still
+    /// finite.
+    pub fn sanitize_state(&mut self) -> [bool; N] {
+        both_finite(flush_lanes(&mut self.x1), flush_lanes(&mut self.y1))
+    }
+
+    /// Reset the state of one lane
+    pub fn reset_lane(&mut self, lane: usize) {
+        self.x1[lane] = T::zero();
+        self.y1[lane] = T::zero();
+    }
+
+    /// Reset all lane state
+    pub fn reset(&mut self) {
+        self.x1 = [T::zero(); N];
+        self.y1 = [T::zero(); N];
+    }
+}
+
+/// State variable filter running `N` channels through shared coefficients
+#[derive(Clone, Copy)]
+pub struct SvfLanes<const N: usize, T = f32> {
+    a1: T,
+    a2: T,
+    a3: T,
+    m0: T,
+    m1: T,
+    m2: T,
+    ic1eq: [T; N],
+    ic2eq: [T; N],
+}
+
+impl<const N: usize, T: Sample> Default for SvfLanes<N, T> {
+    fn default() -> Self {
+        Self {
+            a1: T::zero(),
+            a2: T::zero(),
+            a3: T::zero(),
+            m0: T::zero(),
+            m1: T::zero(),
+            m2: T::zero(),
+            ic1eq: [T::zero(); N],
+            ic2eq: [T::zero(); N],
+        }
+    }
+}
+
+impl<const N: usize, T: Sample> SvfLanes<N, T> {
+    /// Take coefficients from a scalar section, keeping lane state
+    pub fn copy_coefficients(&mut self, source: &SvfState<T>) {
+        [self.a1, self.a2, self.a3, self.m0, self.m1, self.m2] = source.coefficients();
+    }
+
+    /// Process one frame, one sample per lane
+    #[inline]
+    pub fn process(&mut self, input: [T; N]) -> [T; N] {
+        let two = T::from_f64(2.0);
+        let mut output = [T::zero(); N];
+        for (((y, &x), ic1eq), ic2eq) in output
+            .iter_mut()
+            .zip(&input)
+            .zip(&mut self.ic1eq)
+            .zip(&mut self.ic2eq)
+        {
+            let v3 = x - *ic2eq;
+            let v1 = self.a1 * *ic1eq + self.a2 * v3;
+            let v2 = *ic2eq + self.a2 * *ic1eq + self.a3 * v3;
+            *ic1eq = two * v1 - *ic1eq;
+            *ic2eq = two * v2 - *ic2eq;
+            *y = self.m0 * x + self.m1 * v1 + self.m2 * v2;
+        }
+        output
+    }
+
+    /// Flush denormal lane state to zero. Returns which lanes are still
+    /// finite.
+    pub fn sanitize_state(&mut self) -> [bool; N] {
+        both_finite(flush_lanes(&mut self.ic1eq), flush_lanes(&mut self.ic2eq))
+    }
+
+    /// Reset the state of one lane
+    pub fn reset_lane(&mut self, lane: usize) {
+        self.ic1eq[lane] = T::zero();
+        self.ic2eq[lane] = T::zero();
+    }
+
+    /// Reset all lane state
+    pub fn reset(&mut self) {
+        self.ic1eq = [T::zero(); N];
+        self.ic2eq = [T::zero(); N];
+    }
+}
+
+/// One-pole SVF stage running `N` channels through shared coefficients
+#[derive(Clone, Copy)]
+pub struct OnePoleLanes<const N: usize, T = f32> {
+    gain: T,
+    hp_mix: T,
+    lp_mix: T,
+    s: [T; N],
+}
+
+impl<const N: usize, T: Sample> Default for OnePoleLanes<N, T> {
+    fn default() -> Self {
+        Self {
+            gain: T::zero(),
+            hp_mix: T::zero(),
+            lp_mix: T::zero(),
+            s: [T::zero(); N],
+        }
+    }
+}
+
+impl<const N: usize, T: Sample> OnePoleLanes<N, T> {
+    /// Take coefficients from a scalar stage, keeping lane state
+    pub fn copy_coefficients(&mut self, source: &OnePoleState<T>) {
+        [self.gain, self.hp_mix, self.lp_mix] = source.coefficients();
+    }
+
+    /// Process one frame, one sample per lane
+    #[inline]
+    pub fn process(&mut self, input: [T; N]) -> [T; N] {
+        let mut output = [T::zero(); N];
+        for ((y, &x), s) in output.iter_mut().zip(&input).zip(&mut self.s) {
+            let v = (x - *s) * self.gain;
+            let lp = v + *s;
+            *s = lp + v;
+            *y = self.hp_mix * (x - lp) + self.lp_mix * lp;
+        }
+        output
+    }
+
+    /// Flush denormal lane state to zero. Returns which lanes are still
+    /// finite.
+    pub fn sanitize_state(&mut self) -> [bool; N] {
+        flush_lanes(&mut self.s)
+    }
+
+    /// Reset the state of one lane
+    pub fn reset_lane(&mut self, lane: usize) {
+        self.s[lane] = T::zero();
+    }
+
+    /// Reset all lane state
+    pub fn reset(&mut self) {
+        self.s = [T::zero(); N];
+    }
+}
+
+/// Filter chain running `N` channel-interleaved lanes with the coefficients
+/// of a scalar [`FilterChain`] of the same precision
+#[derive(Clone, Copy)]
+pub struct FilterChainLanes<const N: usize, T = f32> {
+    biquad_stages: [BiquadLanes<N, T>; MAX_SECTIONS],
+    first_order: FirstOrderLanes<N, T>,
+    svf_stages: [SvfLanes<N, T>; MAX_SECTIONS],
+    one_pole: OnePoleLanes<N, T>,
+    // Stage counts per engine; the inactive engine's are zero
+    active_biquads: usize,
+    use_first_order: bool,
+    active_svfs: usize,
+    use_one_pole: bool,
+    engine: FilterEngine,
+    /// Fade back in of each lane after it reset itself
+    recovery: [Recovery; N],
+}
+
+impl<const N: usize, T: Sample> Default for FilterChainLanes<N, T> {
+    fn default() -> Self {
+        Self {
+            biquad_stages: [BiquadLanes::default(); MAX_SECTIONS],
+            first_order: FirstOrderLanes::default(),
+            svf_stages: [SvfLanes::default(); MAX_SECTIONS],
+            one_pole: OnePoleLanes::default(),
+            active_biquads: 0,
+            use_first_order: false,
+            active_svfs: 0,
+            use_one_pole: false,
+            engine: FilterEngine::default(),
+            recovery: [Recovery::default(); N],
+        }
+    }
+}
+
+impl<const N: usize, T: Sample> FilterChainLanes<N, T> {
+    /// Take the active stages and coefficients of a scalar chain. State
+    /// is kept unless the chain switched engines.
+    pub fn copy_coefficients(&mut self, chain: &FilterChain<T>) {
+        self.ramp_coefficients(chain, 0);
+    }
+
+    /// Like `copy_coefficients`, but move the biquad and first-order
+    /// coefficients linearly over the next `samples` frames. A chain with a
+    /// different set of stages is taken at once. The SVF stages always
+    /// take theirs at once, as they follow every sample.
+    pub fn ramp_coefficients(&mut self, chain: &FilterChain<T>, samples: u32) {
+        let same_stages = chain.engine() == self.engine
+            && chain.biquads().len() == self.active_biquads
+            && chain.first_order().is_some() == self.use_first_order;
+        let samples = if same_stages { samples } else { 0 };
+        if chain.engine() != self.engine {
+            self.engine = chain.engine();
+            self.reset();
+        }
+
+        // Stages joining the chain start from silence rather than from
+        // whatever they were left with
+        let biquads = chain.biquads();
+        for stage in &mut self.biquad_stages[self.active_biquads.min(biquads.len())..biquads.len()]
+        {
+            stage.reset();
+        }
+        for (stage, source) in self.biquad_stages.iter_mut().zip(biquads) {
+            stage.ramp_coefficients(source, samples);
+        }
+        self.active_biquads = biquads.len();
+
+        if chain.first_order().is_some() && !self.use_first_order {
+            self.first_order.reset();
+        }
+        self.use_first_order = chain.first_order().is_some();
+        if let Some(source) = chain.first_order() {
+            self.first_order.ramp_coefficients(source, samples);
+        }
+
+        let svfs = chain.svfs();
+        for stage in &mut self.svf_stages[self.active_svfs.min(svfs.len())..svfs.len()] {
+            stage.reset();
+        }
+        for (stage, source) in self.svf_stages.iter_mut().zip(svfs) {
+            stage.copy_coefficients(source);
+        }
+        self.active_svfs = svfs.len();
+
+        if chain.one_pole().is_some() && !self.use_one_pole {
+            self.one_pole.reset();
+        }
+        self.use_one_pole = chain.one_pole().is_some();
+        if let Some(source) = chain.one_pole() {
+            self.one_pole.copy_coefficients(source);
+        }
+    }
+
+    /// Process one frame, one sample per lane
+    #[inline]
+    pub fn process(&mut self, mut frame: [T; N]) -> [T; N] {
+        if self.use_first_order {
+            frame = self.first_order.process(frame);
+        }
+        if self.use_one_pole {
+            frame = self.one_pole.process(frame);
+        }
+
+        for stage in &mut self.biquad_stages[..self.active_biquads] {
+            frame = stage.process(frame);
+        }
+        for stage in &mut self.svf_stages[..self.active_svfs] {
+            frame = stage.process(frame);
+        }
+
+        for (x, recovery) in frame.iter_mut().zip(&mut self.recovery) {
+            if let Some(gain) = recovery.next_gain() {
+                *x *= T::from_f32(gain);
+            }
+        }
+        frame
+    }
+
+    /// Process a block of frames in place, one stage at a time, then
+    /// `sanitize` the lanes
+    pub fn process_block(&mut self, frames: &mut [[T; N]]) {
+        if self.use_first_order {
+            for frame in frames.iter_mut() {
+                *frame = self.first_order.process(*frame);
+            }
+        }
+
+        if self.use_one_pole {
+            for frame in frames.iter_mut() {
+                *frame = self.one_pole.process(*frame);
+            }
+        }
+
+        for stage in &mut self.biquad_stages[..self.active_biquads] {
+            for frame in frames.iter_mut() {
+                *frame = stage.process(*frame);
+            }
+        }
+        for stage in &mut self.svf_stages[..self.active_svfs] {
+            for frame in frames.iter_mut() {
+                *frame = stage.process(*frame);
+            }
+        }
+
+        for (lane, recovery) in self.recovery.iter_mut().enumerate() {
+            for frame in frames.iter_mut() {
+                let Some(gain) = recovery.next_gain() else {
+                    break;
+                };
+                frame[lane] *= T::from_f32(gain);
+            }
+        }
+
+        if self.sanitize() {
+            for sample in frames.iter_mut().flatten() {
+                if !sample.is_finite() {
+                    *sample = T::zero();
+                }
+            }
+        }
+    }
+
+    /// Flush denormal state to zero and reset any lane that has gone NaN
+    /// or infinite, fading it back in. The other lanes carry on untouched.
+    /// Meant to run once per block. Returns whether a lane was reset.
+    pub fn sanitize(&mut self) -> bool {
+        // Inactive stages are reset before they are used again
+        let mut finite = [true; N];
+        if self.use_first_order {
+            finite = both_finite(finite, self.first_order.sanitize_state());
+        }
+        if self.use_one_pole {
+            finite = both_finite(finite, self.one_pole.sanitize_state());
+        }
+        for stage in &mut self.biquad_stages[..self.active_biquads] {
+            finite = both_finite(finite, stage.sanitize_state());
+        }
+        for stage in &mut self.svf_stages[..self.active_svfs] {
+            finite = both_finite(finite, stage.sanitize_state());
+        }
+
+        let mut recovered = false;
+        for (lane, finite) in finite.into_iter().enumerate() {
+            if !finite {
+                self.reset_lane(lane);
+                self.recovery[lane].trigger();
+                recovered = true;
+            }
+        }
+        recovered
+    }
+
+    /// Number of lane resets after non-finite state so far
+    pub fn recoveries(&self) -> u32 {
+        self.recovery.iter().map(Recovery::count).sum()
+    }
+
+    /// Reset the state of one lane in every stage
+    pub fn reset_lane(&mut self, lane: usize) {
+        self.first_order.reset_lane(lane);
+        for stage in &mut self.biquad_stages {
+            stage.reset_lane(lane);
+        }
+        self.one_pole.reset_lane(lane);
+        for stage in &mut self.svf_stages {
+            stage.reset_lane(lane);
+        }
+    }
+
+    /// Reset all lane states
+    pub fn reset(&mut self) {
+        self.first_order.reset();
+        for stage in &mut self.biquad_stages {
+            stage.reset();
+        }
+        self.one_pole.reset();
+        for stage in &mut self.svf_stages {
+            stage.reset();
+        }
+    }
+}
+
+/// Phase-matching all-pass running `N` lanes with the coefficients of a
+/// scalar [`PhaseMatch`]
+#[derive(Clone, Copy)]
+pub struct PhaseMatchLanes<const N: usize, T = f32> {
+    stages: [FirstOrderLanes<N, T>; MAX_SECTIONS],
+    active: usize,
+    invert: bool,
+}
+
+impl<const N: usize, T: Sample> Default for PhaseMatchLanes<N, T> {
+    fn default() -> Self {
+        Self {
+            stages: [FirstOrderLanes::default(); MAX_SECTIONS],
+            active: 0,
+            invert: false,
+        }
+    }
+}
+
+impl<const N: usize, T: Sample> PhaseMatchLanes<N, T> {
+    /// Take the stages of a scalar all-pass, keeping lane state
+    pub fn copy_coefficients(&mut self, source: &PhaseMatch<T>) {
+        self.ramp_coefficients(source, 0);
+    }
+
+    /// Like `copy_coefficients`, but move the coefficients linearly over
+    /// the next `samples` frames, unless the number of stages changed
+    pub fn ramp_coefficients(&mut self, source: &PhaseMatch<T>, samples: u32) {
+        let stages = source.stages();
+        let samples = if stages.len() == self.active {
+            samples
+        } else {
+            0
+        };
+        for (stage, source) in self.stages.iter_mut().zip(stages) {
+            stage.ramp_coefficients(source, samples);
+        }
+        self.active = stages.len();
+        self.invert = source.inverted();
+    }
+
+    /// Process one frame, one sample per lane
+    #[inline]
+    pub fn process(&mut self, mut frame: [T; N]) -> [T; N] {
+        for stage in &mut self.stages[..self.active] {
+            frame = stage.process(frame);
+        }
+        if self.invert {
+            frame.map(|x| -x)
+        } else {
+            frame
+        }
+    }
+
+    /// Flush denormal lane state to zero and reset any lane that has gone
+    /// NaN or infinite. Returns whether a lane was reset.
+    pub fn sanitize_state(&mut self) -> bool {
+        let mut finite = [true; N];
+        for stage in &mut self.stages[..self.active] {
+            finite = both_finite(finite, stage.sanitize_state());
+        }
+
+        let mut recovered = false;
+        for (lane, finite) in finite.into_iter().enumerate() {
+            if !finite {
+                for stage in &mut self.stages {
+                    stage.reset_lane(lane);
+                }
+                recovered = true;
+            }
+        }
+        recovered
+    }
+
+    /// Reset all lane states
+    pub fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{FilterSlope, FilterType};
+
+    #[test]
+    fn test_lanes_match_scalar() {
+        for engine in [FilterEngine::Biquad, FilterEngine::Svf] {
+            let mut chain = FilterChain::default();
+            chain.set_engine(engine);
+            chain.update_coefficients(
+                48000.0,
+                250.0,
+                2.0,
+                0.0,
+                FilterType::HighPass,
+                FilterSlope::Slope18dB,
+            );
+            let mut scalar = [chain; 4];
+            let mut lanes = FilterChainLanes::<4>::default();
+            lanes.copy_coefficients(&chain);
+
+            let mut frames: Vec<[f32; 4]> = (0..256)
+                .map(|n| {
+                    std::array::from_fn(|lane| ((n * 31 + lane * 17) % 64) as f32 / 32.0 - 1.0)
+                })
+                .collect();
+            let expected: Vec<[f32; 4]> = frames
+                .iter()
+                .map(|frame| std::array::from_fn(|lane| scalar[lane].process(frame[lane])))
+                .collect();
+            lanes.process_block(&mut frames);
+
+            assert_eq!(frames, expected, "{:?}", engine);
+        }
+    }
+
+    #[test]
+    fn test_coefficient_ramp_moves_in_small_steps() {
+        let design = |cutoff: f32| {
+            let mut chain = FilterChain::default();
+            chain.update_coefficients(
+                48000.0,
+                cutoff,
+                0.707,
+                0.0,
+                FilterType::HighPass,
+                FilterSlope::Slope18dB,
+            );
+            chain
+        };
+        let (from, to) = (design(100.0), design(2000.0));
+        let mut lanes = FilterChainLanes::<4>::default();
+        lanes.copy_coefficients(&from);
+        lanes.ramp_coefficients(&to, 16);
+
+        let coefficients = |lanes: &FilterChainLanes<4>| {
+            let stage = &lanes.biquad_stages[0];
+            let first = &lanes.first_order;
+            [
+                stage.b0, stage.b1, stage.b2, stage.a1, stage.a2, first.b0, first.b1, first.a1,
+            ]
+        };
+        let start = coefficients(&lanes);
+        let target = [
+            to.biquads()[0].coefficients().as_slice(),
+            to.first_order().unwrap().coefficients().as_slice(),
+        ]
+        .concat();
+        let mut previous = start;
+        for _ in 0..16 {
+            lanes.process([0.0; 4]);
+            let current = coefficients(&lanes);
+            // No step is bigger than an even share of the whole move
+            for ((c, p), (s, t)) in current.iter().zip(&previous).zip(start.iter().zip(&target)) {
+                assert!((c - p).abs() <= (t - s).abs() / 16.0 + 1e-6);
+            }
+            previous = current;
+        }
+        assert_eq!(previous.as_slice(), target.as_slice());
+    }
+
+    #[test]
+    fn test_nan_recovery() {
+        let mut chain = FilterChain::default();
+        chain.update_coefficients(
+            48000.0,
+            250.0,
+            0.707,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope24dB,
+        );
+        let mut lanes = FilterChainLanes::<4>::default();
+        lanes.copy_coefficients(&chain);
+        let mut clean = lanes;
+
+        let mut frames = vec![[0.5; 4]; 64];
+        let mut expected = frames.clone();
+        frames[10][2] = f32::NAN;
+        lanes.process_block(&mut frames);
+        clean.process_block(&mut expected);
+        assert_eq!(lanes.recoveries(), 1);
+        assert!(frames.iter().flatten().all(|x| x.is_finite()));
+
+        // The bad lane comes back while the clean ones carry on untouched
+        let mut tail = vec![[0.5; 4]; 1024];
+        let mut expected_tail = tail.clone();
+        lanes.process_block(&mut tail);
+        clean.process_block(&mut expected_tail);
+        assert_eq!(lanes.recoveries(), 1);
+        assert!(tail[1023].iter().all(|x| x.is_finite() && x.abs() < 0.5));
+        for lane in [0, 1, 3] {
+            assert!(frames
+                .iter()
+                .chain(&tail)
+                .zip(expected.iter().chain(&expected_tail))
+                .all(|(frame, expected)| frame[lane] == expected[lane]));
+        }
+    }
+
+    #[test]
+    fn test_phase_match_nan_recovery() {
+        let mut chain = FilterChain::default();
+        chain.update_coefficients(
+            48000.0,
+            250.0,
+            0.707,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope18dB,
+        );
+        let mut follower = PhaseMatch::default();
+        follower.follow(&chain, 48000.0, 48000.0, FilterType::HighPass);
+        let mut dry = PhaseMatchLanes::<4>::default();
+        dry.copy_coefficients(&follower);
+
+        let mut output = dry.process([f32::NAN, 0.5, 0.5, 0.5]);
+        assert!(output[0].is_nan());
+        assert!(dry.sanitize_state());
+        assert!(!dry.sanitize_state());
+        for _ in 0..64 {
+            output = dry.process([0.5; 4]);
+            assert!(output.iter().all(|x| x.is_finite()));
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod alignment;
+pub mod filter;
+pub mod lanes;
+pub mod svf;
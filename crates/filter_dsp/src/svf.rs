@@ -0,0 +1,353 @@
+use num_complex::Complex64;
+
+use crate::filter::{
+    angular_frequency, flush_state, prewarp, section_response, FilterType, FrequencyResponse,
+    Sample,
+};
+
+/// Zero-delay-feedback state variable filter (Zavalishin's topology-preserving
+/// transform, in Simper's trapezoidal form).
+///
+/// The state is held in the integrators rather than in past outputs, so
+/// the coefficients can change every sample without blowing up. The
+/// steady-state response matches the RBJ biquads exactly.
+#[derive(Clone, Copy, Default)]
+pub struct SvfState<T = f32> {
+    /// Pre-warped cutoff, tan(pi * f / fs)
+    g: T,
+    /// Damping, 1 / Q
+    k: T,
+    a1: T,
+    a2: T,
+    a3: T,
+    // Output mix of input, band-pass and low-pass
+    m0: T,
+    m1: T,
+    m2: T,
+    // Integrator states
+    ic1eq: T,
+    ic2eq: T,
+}
+
+impl<T: Sample> SvfState<T> {
+    /// Calculate coefficients for the given response type
+    pub fn set(
+        &mut self,
+        filter_type: FilterType,
+        sample_rate: f32,
+        freq: f32,
+        q: f32,
+        gain_db: f32,
+    ) {
+        let (zero, one) = (T::zero(), T::one());
+        let g = prewarp::<T>(sample_rate, freq);
+        let q = T::from_f32(q);
+        let k = one / q;
+        let a = T::from_f64(10.0f64.powf(gain_db as f64 / 40.0));
+
+        let (g, k, [m0, m1, m2]) = match filter_type {
+            FilterType::HighPass => (g, k, [one, -k, -one]),
+            FilterType::LowPass => (g, k, [zero, zero, one]),
+            FilterType::BandPass => (g, k, [zero, k, zero]),
+            FilterType::Notch => (g, k, [one, -k, zero]),
+            FilterType::Peak => {
+                let k = one / (q * a);
+                (g, k, [one, k * (a * a - one), zero])
+            }
+            FilterType::LowShelf => (g / a.sqrt(), k, [one, k * (a - one), a * a - one]),
+            FilterType::HighShelf => (g * a.sqrt(), k, [a * a, k * (one - a) * a, one - a * a]),
+            FilterType::AllPass => (g, k, [one, -T::from_f64(2.0) * k, zero]),
+        };
+
+        self.g = g;
+        self.k = k;
+        self.a1 = one / (one + g * (g + k));
+        self.a2 = g * self.a1;
+        self.a3 = g * self.a2;
+        self.m0 = m0;
+        self.m1 = m1;
+        self.m2 = m2;
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process(&mut self, input: T) -> T {
+        let two = T::from_f64(2.0);
+        let v3 = input - self.ic2eq;
+        let v1 = self.a1 * self.ic1eq + self.a2 * v3;
+        let v2 = self.ic2eq + self.a2 * self.ic1eq + self.a3 * v3;
+        self.ic1eq = two * v1 - self.ic1eq;
+        self.ic2eq = two * v2 - self.ic2eq;
+        self.m0 * input + self.m1 * v1 + self.m2 * v2
+    }
+
+    /// Process a block of samples in place
+    pub fn process_block(&mut self, samples: &mut [T]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Copy coefficients from another section, keeping this section's state
+    pub fn copy_coefficients(&mut self, other: &Self) {
+        *self = Self {
+            ic1eq: self.ic1eq,
+            ic2eq: self.ic2eq,
+            ..*other
+        };
+    }
+
+    /// Processing coefficients as [a1, a2, a3, m0, m1, m2]
+    pub fn coefficients(&self) -> [T; 6] {
+        [self.a1, self.a2, self.a3, self.m0, self.m1, self.m2]
+    }
+
+    /// Integrator states as [ic1eq, ic2eq]
+    pub fn state(&self) -> [T; 2] {
+        [self.ic1eq, self.ic2eq]
+    }
+
+    /// Flush denormal state to zero. Returns false if the state is no
+    /// longer finite.
+    pub fn sanitize_state(&mut self) -> bool {
+        flush_state(&mut self.ic1eq) & flush_state(&mut self.ic2eq)
+    }
+
+    /// Natural frequency of the section's poles
+    pub fn pole_frequency(&self, sample_rate: f32) -> f32 {
+        (self.g.atan() / T::PI()).as_f32() * sample_rate
+    }
+
+    /// Coefficients of the equivalent direct form biquad, as
+    /// [b0, b1, b2, a1, a2]
+    pub fn biquad_coefficients(&self) -> [T; 5] {
+        let (g, k) = (self.g, self.k);
+        let (one, two) = (T::one(), T::from_f64(2.0));
+        let g2 = g * g;
+        let a0 = one + k * g + g2;
+        // Analog numerator n2 s^2 + n1 s + n0, since the input itself is
+        // (s^2 + k s + 1) over the shared denominator
+        let (n2, n1, n0) = (self.m0, self.m0 * k + self.m1, self.m0 + self.m2);
+
+        [
+            (n2 + n1 * g + n0 * g2) / a0,
+            two * (n0 * g2 - n2) / a0,
+            (n2 - n1 * g + n0 * g2) / a0,
+            two * (g2 - one) / a0,
+            (one - k * g + g2) / a0,
+        ]
+    }
+
+    /// Reset filter state
+    pub fn reset(&mut self) {
+        self.ic1eq = T::zero();
+        self.ic2eq = T::zero();
+    }
+}
+
+impl<T: Sample> FrequencyResponse for SvfState<T> {
+    fn response(&self, sample_rate: f32, freq: f32) -> Complex64 {
+        let [b0, b1, b2, a1, a2] = self.biquad_coefficients().map(T::as_f64);
+        let w = angular_frequency(sample_rate, freq);
+        section_response(&[b0, b1, b2], &[1.0, a1, a2], w).0
+    }
+
+    fn group_delay(&self, sample_rate: f32, freq: f32) -> f32 {
+        let [b0, b1, b2, a1, a2] = self.biquad_coefficients().map(T::as_f64);
+        let w = angular_frequency(sample_rate, freq);
+        let (_, delay) = section_response(&[b0, b1, b2], &[1.0, a1, a2], w);
+        (delay / sample_rate as f64) as f32
+    }
+}
+
+/// Zero-delay-feedback one-pole stage for the odd slopes, mixing its
+/// high-pass and low-pass outputs
+#[derive(Clone, Copy, Default)]
+pub struct OnePoleState<T = f32> {
+    /// Pre-warped cutoff, tan(pi * f / fs)
+    g: T,
+    /// Integrator gain, g / (1 + g)
+    gain: T,
+    hp_mix: T,
+    lp_mix: T,
+    s: T,
+}
+
+impl<T: Sample> OnePoleState<T> {
+    /// Calculate coefficients for the given response type.
+    /// Types without a first-order form leave the stage transparent.
+    pub fn set(&mut self, filter_type: FilterType, sample_rate: f32, freq: f32, gain_db: f32) {
+        let (zero, one) = (T::zero(), T::one());
+        let gain = T::from_f64(10.0f64.powf(gain_db as f64 / 20.0));
+
+        (self.hp_mix, self.lp_mix) = match filter_type {
+            FilterType::HighPass => (one, zero),
+            FilterType::LowPass => (zero, one),
+            FilterType::LowShelf => (one, gain),
+            FilterType::HighShelf => (gain, one),
+            FilterType::AllPass => (-one, one),
+            FilterType::BandPass | FilterType::Notch | FilterType::Peak => (one, one),
+        };
+        self.g = prewarp::<T>(sample_rate, freq);
+        self.gain = self.g / (one + self.g);
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process(&mut self, input: T) -> T {
+        let v = (input - self.s) * self.gain;
+        let lp = v + self.s;
+        self.s = lp + v;
+        self.hp_mix * (input - lp) + self.lp_mix * lp
+    }
+
+    /// Process a block of samples in place
+    pub fn process_block(&mut self, samples: &mut [T]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Copy coefficients from another stage, keeping this stage's state
+    pub fn copy_coefficients(&mut self, other: &Self) {
+        *self = Self {
+            s: self.s,
+            ..*other
+        };
+    }
+
+    /// Processing coefficients as [gain, hp_mix, lp_mix]
+    pub fn coefficients(&self) -> [T; 3] {
+        [self.gain, self.hp_mix, self.lp_mix]
+    }
+
+    /// Integrator state
+    pub fn state(&self) -> T {
+        self.s
+    }
+
+    /// Frequency of the stage's pole
+    pub fn pole_frequency(&self, sample_rate: f32) -> f32 {
+        (self.g.atan() / T::PI()).as_f32() * sample_rate
+    }
+
+    /// Coefficients of the equivalent direct form stage, as [b0, b1, a1]
+    pub fn first_order_coefficients(&self) -> [T; 3] {
+        let g = self.g;
+        let a0 = T::one() + g;
+
+        [
+            (self.hp_mix + self.lp_mix * g) / a0,
+            (self.lp_mix * g - self.hp_mix) / a0,
+            (g - T::one()) / a0,
+        ]
+    }
+
+    /// Flush denormal state to zero. Returns false if the state is no
+    /// longer finite.
+    pub fn sanitize_state(&mut self) -> bool {
+        flush_state(&mut self.s)
+    }
+
+    /// Reset filter state
+    pub fn reset(&mut self) {
+        self.s = T::zero();
+    }
+}
+
+impl<T: Sample> FrequencyResponse for OnePoleState<T> {
+    fn response(&self, sample_rate: f32, freq: f32) -> Complex64 {
+        let [b0, b1, a1] = self.first_order_coefficients().map(T::as_f64);
+        let w = angular_frequency(sample_rate, freq);
+        section_response(&[b0, b1], &[1.0, a1], w).0
+    }
+
+    fn group_delay(&self, sample_rate: f32, freq: f32) -> f32 {
+        let [b0, b1, a1] = self.first_order_coefficients().map(T::as_f64);
+        let w = angular_frequency(sample_rate, freq);
+        let (_, delay) = section_response(&[b0, b1], &[1.0, a1], w);
+        (delay / sample_rate as f64) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{BiquadState, FirstOrderHPState};
+    use std::f32::consts::PI;
+
+    const TYPES: [FilterType; 8] = [
+        FilterType::HighPass,
+        FilterType::LowPass,
+        FilterType::BandPass,
+        FilterType::Notch,
+        FilterType::Peak,
+        FilterType::LowShelf,
+        FilterType::HighShelf,
+        FilterType::AllPass,
+    ];
+
+    #[test]
+    fn test_matches_biquad() {
+        for filter_type in TYPES {
+            for (freq, q, gain_db) in [(80.0, 0.707, 6.0), (2500.0, 4.0, -9.0)] {
+                let mut biquad = BiquadState::<f32>::default();
+                let mut svf = SvfState::<f32>::default();
+                biquad.set(filter_type, 48000.0, freq, q, gain_db);
+                svf.set(filter_type, 48000.0, freq, q, gain_db);
+
+                for (a, b) in svf.biquad_coefficients().iter().zip(biquad.coefficients()) {
+                    assert!(
+                        (a - b).abs() < 1e-4,
+                        "{:?}: {:?} vs {:?}",
+                        filter_type,
+                        svf.biquad_coefficients(),
+                        biquad.coefficients()
+                    );
+                }
+
+                // Same output once the topologies have settled
+                let mut max_error = 0.0f32;
+                for n in 0..4800 {
+                    let x = (2.0 * PI * 1000.0 * n as f32 / 48000.0).sin();
+                    let error = (svf.process(x) - biquad.process(x)).abs();
+                    if n > 2400 {
+                        max_error = max_error.max(error);
+                    }
+                }
+                assert!(max_error < 1e-3, "{:?}: error {}", filter_type, max_error);
+            }
+
+            let mut first_order = FirstOrderHPState::<f32>::default();
+            let mut one_pole = OnePoleState::<f32>::default();
+            first_order.set(filter_type, 48000.0, 300.0, 6.0);
+            one_pole.set(filter_type, 48000.0, 300.0, 6.0);
+            for freq in [30.0, 300.0, 3000.0] {
+                let error =
+                    (one_pole.response(48000.0, freq) - first_order.response(48000.0, freq)).norm();
+                assert!(error < 1e-5, "{:?} first order at {} Hz", filter_type, freq);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stable_under_audio_rate_modulation() {
+        let sample_rate = 48000.0;
+        let mut svf = SvfState::<f32>::default();
+        let mut peak = 0.0f32;
+        for n in 0..48000 {
+            // Sweep 50 Hz to 15 kHz at 2 kHz with a resonant high-pass
+            let t = n as f32 / sample_rate;
+            let sweep = 0.5 + 0.5 * (2.0 * PI * 2000.0 * t).sin();
+            let freq = 50.0 * 300.0f32.powf(sweep);
+            svf.set(FilterType::HighPass, sample_rate, freq, 10.0, 0.0);
+
+            let x = if n % 97 < 48 { 1.0 } else { -1.0 };
+            let y = svf.process(x);
+            assert!(y.is_finite());
+            peak = peak.max(y.abs());
+        }
+        // Bounded by the resonance peak, nowhere near a blow-up
+        assert!(peak < 40.0, "peak {}", peak);
+    }
+}
@@ -0,0 +1,394 @@
+use std::f32::consts::PI;
+
+use crate::filter::{flush_state, FilterSlope, Recovery};
+
+/// Lowest oversampling factor the nonlinear stages run at, which keeps
+/// their aliasing below -90 dB
+pub const MIN_FACTOR: usize = 8;
+
+/// Highest Q of the resonance parameter
+const MAX_Q: f32 = 10.0;
+
+/// Loop gain past unity at maximum Q when self-oscillation is enabled
+const SELF_OSCILLATION_EXCESS: f32 = 0.05;
+
+/// Analog-modelled nonlinear high-pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CharacterModel {
+    /// Two-pole Sallen-Key with a saturating resonance loop, MS-20 style
+    #[default]
+    SallenKey,
+    /// Four-pole transistor ladder with the nonlinearity at its input
+    Ladder,
+    /// Two-pole filter with asymmetric diodes clipping the feedback
+    Diode,
+}
+
+impl CharacterModel {
+    /// Small-signal slope of the model
+    pub fn slope(self) -> FilterSlope {
+        match self {
+            CharacterModel::SallenKey | CharacterModel::Diode => FilterSlope::Slope12dB,
+            CharacterModel::Ladder => FilterSlope::Slope24dB,
+        }
+    }
+
+    /// Saturating transfer curve of the model
+    #[inline]
+    fn shape(self, x: f32) -> f32 {
+        match self {
+            CharacterModel::SallenKey | CharacterModel::Ladder => tanh(x),
+            CharacterModel::Diode => diode(x),
+        }
+    }
+}
+
+/// Rational tanh approximation, exact at the origin and saturating at +-1
+#[inline]
+fn tanh(x: f32) -> f32 {
+    let x = x.clamp(-3.0, 3.0);
+    let x2 = x * x;
+    x * (27.0 + x2) / (27.0 + 9.0 * x2)
+}
+
+/// Diode pair clipping at +1 and, with a lower forward voltage, -0.6, so
+/// the clipping adds even harmonics
+#[inline]
+fn diode(x: f32) -> f32 {
+    if x >= 0.0 {
+        tanh(x)
+    } else {
+        0.6 * tanh(x / 0.6)
+    }
+}
+
+/// Gain of `shape` at `x` relative to its small-signal slope of one. The
+/// zero-delay feedback loops are solved with this gain, taken from the
+/// previous sample, in place of the curve itself.
+#[inline]
+fn secant_gain(model: CharacterModel, x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        model.shape(x) / x
+    }
+}
+
+/// Nonlinear resonant high-pass for `N` channel lanes. Drive scales the
+/// signal into the nonlinearities and back out again, so it changes the
+/// amount of saturation rather than the level.
+#[derive(Clone, Copy)]
+pub struct CharacterLanes<const N: usize> {
+    model: CharacterModel,
+    /// Pre-warped cutoff, tan(pi * f / fs)
+    g: f32,
+    /// Resonance loop gain, 1 at the edge of self-oscillation
+    resonance: f32,
+    drive: f32,
+    /// Integrator states, two for the Sallen-Key and diode models, four for
+    /// the ladder
+    s: [[f32; N]; 4],
+    /// Signal at the nonlinearity on the previous sample
+    last: [f32; N],
+    /// Fade back in of each lane after it reset itself
+    recovery: [Recovery; N],
+}
+
+impl<const N: usize> Default for CharacterLanes<N> {
+    fn default() -> Self {
+        Self {
+            model: CharacterModel::default(),
+            g: 0.0,
+            resonance: 0.0,
+            drive: 1.0,
+            s: [[0.0; N]; 4],
+            last: [0.0; N],
+            recovery: [Recovery::default(); N],
+        }
+    }
+}
+
+impl<const N: usize> CharacterLanes<N> {
+    /// Set the model and its controls. Changing the model clears the state.
+    /// `q` maps to the resonance like the clean filter's Q; with
+    /// `self_oscillation` the loop gain passes unity as it nears the top of
+    /// its range.
+    pub fn set(
+        &mut self,
+        model: CharacterModel,
+        sample_rate: f32,
+        freq: f32,
+        q: f32,
+        drive_db: f32,
+        self_oscillation: bool,
+    ) {
+        if model != self.model {
+            self.model = model;
+            self.reset();
+        }
+
+        self.g = (PI * freq / sample_rate).tan();
+        self.drive = 10.0f32.powf(drive_db / 20.0);
+        // Same small-signal damping as a two-pole section of this Q
+        self.resonance = (1.0 - 0.5 / q).max(0.0);
+        if self_oscillation {
+            let top = ((q - 0.5 * MAX_Q) / (0.5 * MAX_Q)).clamp(0.0, 1.0);
+            self.resonance += (SELF_OSCILLATION_EXCESS + 0.5 / MAX_Q) * top * top;
+        }
+    }
+
+    /// Process one frame, one sample per lane
+    #[inline]
+    pub fn process(&mut self, input: [f32; N]) -> [f32; N] {
+        let mut output = match self.model {
+            CharacterModel::SallenKey | CharacterModel::Diode => self.process_two_pole(input),
+            CharacterModel::Ladder => self.process_ladder(input),
+        };
+
+        for (y, recovery) in output.iter_mut().zip(&mut self.recovery) {
+            if let Some(gain) = recovery.next_gain() {
+                *y *= gain;
+            }
+        }
+        output
+    }
+
+    /// Two-pole state variable core whose damping comes from a saturating
+    /// band-pass feedback, so loud resonance compresses instead of growing
+    #[inline]
+    fn process_two_pole(&mut self, input: [f32; N]) -> [f32; N] {
+        let model = self.model;
+        let (g, drive) = (self.g, self.drive);
+        let mut output = [0.0; N];
+
+        for lane in 0..N {
+            let x = model.shape(drive * input[lane]);
+            let [s1, s2] = [self.s[0][lane], self.s[1][lane]];
+
+            let gain = secant_gain(model, self.last[lane]);
+            let damping = 2.0 * (1.0 - self.resonance * gain);
+            let hp = (x - (damping + g) * s1 - s2) / (1.0 + g * (damping + g));
+            let bp = g * hp + s1;
+            let lp = g * bp + s2;
+            self.s[0][lane] = g * hp + bp;
+            self.s[1][lane] = g * bp + lp;
+            self.last[lane] = bp;
+
+            output[lane] = hp / drive;
+        }
+        output
+    }
+
+    /// Four one-pole high-passes with negative feedback around them. The
+    /// input is scaled up by the feedback amount so the passband level holds
+    /// as resonance rises.
+    #[inline]
+    fn process_ladder(&mut self, input: [f32; N]) -> [f32; N] {
+        let model = self.model;
+        let (drive, k) = (self.drive, 4.0 * self.resonance);
+        // Each stage is hp = a * (x - s)
+        let a = 1.0 / (1.0 + self.g);
+        let a4 = a * a * a * a;
+        let mut output = [0.0; N];
+
+        for lane in 0..N {
+            let x = drive * input[lane] * (1.0 + k);
+            // Output as a function of the ladder input: y = a^4 u + offset
+            let offset = -(a4 * self.s[0][lane]
+                + a * a * a * self.s[1][lane]
+                + a * a * self.s[2][lane]
+                + a * self.s[3][lane]);
+
+            let gain = secant_gain(model, self.last[lane]);
+            let mut y = gain * (x - k * offset) / (1.0 + gain * k * a4);
+            self.last[lane] = x - k * (a4 * y + offset);
+
+            for s in &mut self.s {
+                let hp = a * (y - s[lane]);
+                s[lane] = 2.0 * (y - hp) - s[lane];
+                y = hp;
+            }
+
+            output[lane] = y / drive;
+        }
+        output
+    }
+
+    /// Flush denormal state to zero and reset any lane that has gone NaN
+    /// or infinite, fading it back in. The other lanes carry on untouched.
+    /// Meant to run once per block. Returns whether a lane was reset.
+    pub fn sanitize(&mut self) -> bool {
+        let mut recovered = false;
+        for lane in 0..N {
+            let finite = self
+                .s
+                .iter_mut()
+                .map(|s| &mut s[lane])
+                .chain([&mut self.last[lane]])
+                .fold(true, |finite, x| flush_state(x) & finite);
+            if !finite {
+                for s in &mut self.s {
+                    s[lane] = 0.0;
+                }
+                self.last[lane] = 0.0;
+                self.recovery[lane].trigger();
+                recovered = true;
+            }
+        }
+        recovered
+    }
+
+    /// Reset all lane state
+    pub fn reset(&mut self) {
+        self.s = [[0.0; N]; 4];
+        self.last = [0.0; N];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oversampling::Oversampler;
+    use realfft::RealFftPlanner;
+
+    const MODELS: [CharacterModel; 3] = [
+        CharacterModel::SallenKey,
+        CharacterModel::Ladder,
+        CharacterModel::Diode,
+    ];
+
+    /// Steady-state peak of a sine through one lane
+    fn sine_peak(
+        character: &mut CharacterLanes<1>,
+        sample_rate: f32,
+        freq: f32,
+        level: f32,
+    ) -> f32 {
+        let mut peak = 0.0f32;
+        for n in 0..(sample_rate as usize / 2) {
+            let x = level * (2.0 * PI * freq * n as f32 / sample_rate).sin();
+            let [y] = character.process([x]);
+            if n > sample_rate as usize / 4 {
+                peak = peak.max(y.abs());
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn test_small_signals_are_high_passed() {
+        for model in MODELS {
+            let mut character = CharacterLanes::<1>::default();
+            character.set(model, 48000.0, 500.0, 0.707, 0.0, false);
+
+            let pass = sine_peak(&mut character, 48000.0, 8000.0, 0.01) / 0.01;
+            let stop = sine_peak(&mut character, 48000.0, 50.0, 0.01) / 0.01;
+            assert!((pass - 1.0).abs() < 0.1, "{:?}: passband {}", model, pass);
+
+            let expected_db = -20.0 * model.slope().order() as f32;
+            let stop_db = 20.0 * stop.log10();
+            assert!(
+                stop_db < expected_db + 10.0,
+                "{:?}: {} dB a decade below the cutoff",
+                model,
+                stop_db
+            );
+        }
+    }
+
+    #[test]
+    fn test_self_oscillation_at_max_resonance() {
+        for model in MODELS {
+            for self_oscillation in [false, true] {
+                let mut character = CharacterLanes::<1>::default();
+                character.set(model, 48000.0, 1000.0, MAX_Q, 0.0, self_oscillation);
+
+                // Kick it, then listen to the tail
+                character.process([0.1]);
+                let mut peak = 0.0f32;
+                for n in 0..96000 {
+                    let [y] = character.process([0.0]);
+                    assert!(y.is_finite());
+                    if n > 48000 {
+                        peak = peak.max(y.abs());
+                    }
+                }
+
+                if self_oscillation {
+                    assert!(peak > 0.05, "{:?} did not oscillate", model);
+                    assert!(peak < 4.0, "{:?} oscillates at {}", model, peak);
+                } else {
+                    assert!(peak < 1e-4, "{:?} rings at {}", model, peak);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_oversampled_aliasing_below_90_db() {
+        const SIZE: usize = 8192;
+        let sample_rate = 48000.0;
+        // Exactly on a bin, so only real aliases land between harmonics.
+        // The fourth harmonic folds back from 29.2 kHz to 18.8 kHz.
+        let bin = 1246;
+        let freq = bin as f32 * sample_rate / SIZE as f32;
+        // Aliases above 20 kHz land in the half-band transition
+        let audible_bins = 20000 * SIZE / sample_rate as usize;
+
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(SIZE);
+        for model in MODELS {
+            let mut character = CharacterLanes::<1>::default();
+            let mut oversampler = Oversampler::<1>::default();
+            oversampler.set_factor(MIN_FACTOR);
+            character.set(
+                model,
+                sample_rate * MIN_FACTOR as f32,
+                200.0,
+                2.0,
+                12.0,
+                false,
+            );
+
+            let mut frames: Vec<[f32; 1]> = (0..SIZE * 2)
+                .map(|n| {
+                    let phase = std::f64::consts::TAU * freq as f64 * n as f64 / sample_rate as f64;
+                    [0.5 * phase.sin() as f32]
+                })
+                .collect();
+            oversampler.process_block(&mut frames, |frames| {
+                for frame in frames {
+                    *frame = character.process(*frame);
+                }
+            });
+            let mut input: Vec<f32> = frames[SIZE..]
+                .iter()
+                .enumerate()
+                .map(|(n, [y])| {
+                    // Four-term Blackman-Harris, sidelobes below -92 dB
+                    let t = 2.0 * PI * n as f32 / SIZE as f32;
+                    let window = 0.35875 - 0.48829 * t.cos() + 0.14128 * (2.0 * t).cos()
+                        - 0.01168 * (3.0 * t).cos();
+                    y * window
+                })
+                .collect();
+            let mut spectrum = fft.make_output_vec();
+            fft.process(&mut input, &mut spectrum).unwrap();
+
+            let magnitudes: Vec<f32> = spectrum.iter().map(|bin| bin.norm()).collect();
+            let fundamental = magnitudes[bin];
+            let worst_alias = magnitudes[..audible_bins]
+                .iter()
+                .enumerate()
+                // Skip DC and every harmonic, with the window's main lobe
+                .filter(|&(i, _)| {
+                    let nearest = (i + bin / 2) / bin * bin;
+                    i > 8 && i.abs_diff(nearest) > 4
+                })
+                .map(|(_, &magnitude)| magnitude)
+                .fold(0.0f32, f32::max);
+
+            let alias_db = 20.0 * (worst_alias / fundamental).log10();
+            assert!(alias_db < -90.0, "{:?}: aliasing at {} dB", model, alias_db);
+        }
+    }
+}
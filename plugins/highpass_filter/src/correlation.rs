@@ -0,0 +1,76 @@
+use crate::envelope::time_coefficient;
+
+/// Averaging time of the correlation
+const INTEGRATION_MS: f32 = 300.0;
+
+/// Power below which the pair counts as silent
+const SILENCE: f32 = 1e-10;
+
+/// Running phase correlation of a stereo pair, from -1 (out of phase)
+/// through 0 (unrelated) to +1 (mono)
+#[derive(Clone, Copy, Default)]
+pub struct CorrelationMeter {
+    coeff: f32,
+    /// Averaged products of left and right
+    lr: f32,
+    ll: f32,
+    rr: f32,
+}
+
+impl CorrelationMeter {
+    /// Set the averaging time for a new sample rate
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.coeff = time_coefficient(sample_rate, INTEGRATION_MS);
+    }
+
+    /// Add one stereo sample
+    #[inline]
+    pub fn process(&mut self, left: f32, right: f32) {
+        let average = |state: &mut f32, value: f32| *state = value + self.coeff * (*state - value);
+        average(&mut self.lr, left * right);
+        average(&mut self.ll, left * left);
+        average(&mut self.rr, right * right);
+    }
+
+    /// Current correlation, 0 while silent
+    pub fn value(&self) -> f32 {
+        let power = (self.ll * self.rr).sqrt();
+        if power < SILENCE {
+            0.0
+        } else {
+            (self.lr / power).clamp(-1.0, 1.0)
+        }
+    }
+
+    /// Forget the averaged signal
+    pub fn reset(&mut self) {
+        self.lr = 0.0;
+        self.ll = 0.0;
+        self.rr = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    #[test]
+    fn test_correlation_of_mono_inverted_and_quadrature() {
+        let sample_rate = 48000.0;
+        let measure = |phase: f32| {
+            let mut meter = CorrelationMeter::default();
+            meter.set_sample_rate(sample_rate);
+            for n in 0..sample_rate as usize {
+                let t = TAU * 440.0 * n as f32 / sample_rate;
+                meter.process(t.sin(), (t + phase).sin());
+            }
+            meter.value()
+        };
+
+        assert!((measure(0.0) - 1.0).abs() < 0.01);
+        assert!((measure(TAU / 2.0) + 1.0).abs() < 0.01);
+        assert!(measure(TAU / 4.0).abs() < 0.05);
+        assert_eq!(CorrelationMeter::default().value(), 0.0);
+    }
+}
@@ -0,0 +1,80 @@
+/// Linear ramp between two signals, 0 for the first and 1 for the second
+#[derive(Clone, Copy, Default)]
+pub struct Crossfade {
+    position: f32,
+    target: f32,
+    /// Position change per sample
+    step: f32,
+}
+
+impl Crossfade {
+    /// Set the time a full fade takes
+    pub fn set_time(&mut self, sample_rate: f32, time_ms: f32) {
+        self.step = 1.0 / (0.001 * time_ms * sample_rate).max(1.0);
+    }
+
+    /// Start moving towards `target`, from wherever the fade is now
+    pub fn fade_to(&mut self, target: f32) {
+        self.target = target;
+    }
+
+    /// Start a fade from the first signal over to the second
+    pub fn restart(&mut self) {
+        self.position = 0.0;
+        self.target = 1.0;
+    }
+
+    /// Jump to the target
+    pub fn finish(&mut self) {
+        self.position = self.target;
+    }
+
+    /// Current position, 0 at the first signal
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Whether the position is still moving
+    pub fn is_fading(&self) -> bool {
+        self.position != self.target
+    }
+
+    /// Advance one sample and return the new position
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        self.position = if self.position < self.target {
+            (self.position + self.step).min(self.target)
+        } else {
+            (self.position - self.step).max(self.target)
+        };
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_takes_set_time_and_reverses() {
+        let mut fade = Crossfade::default();
+        fade.set_time(48000.0, 5.0);
+
+        fade.restart();
+        let samples = (0..1000).take_while(|_| fade.next() < 1.0).count() + 1;
+        assert!((239..=241).contains(&samples));
+        assert!(!fade.is_fading());
+
+        // Turning back partway starts from where the fade is
+        fade.fade_to(0.0);
+        for _ in 0..60 {
+            fade.next();
+        }
+        fade.fade_to(1.0);
+        assert!((fade.next() - 0.75).abs() < 0.01);
+        assert!(fade.is_fading());
+
+        fade.finish();
+        assert!(!fade.is_fading());
+    }
+}
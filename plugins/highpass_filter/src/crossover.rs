@@ -0,0 +1,104 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::filter::BiquadState;
+
+/// 24 dB/oct Linkwitz-Riley crossover: each band is two Butterworth
+/// sections in series, and the bands sum back to an all-pass
+#[derive(Clone, Copy, Default)]
+pub struct LinkwitzRiley {
+    lowpass: [BiquadState; 2],
+    highpass: [BiquadState; 2],
+}
+
+impl LinkwitzRiley {
+    /// Move the crossover frequency, keeping the filter state
+    pub fn set_frequency(&mut self, sample_rate: f32, freq: f32) {
+        for stage in &mut self.lowpass {
+            stage.set_lowpass(sample_rate, freq, FRAC_1_SQRT_2);
+        }
+        for stage in &mut self.highpass {
+            stage.set_highpass(sample_rate, freq, FRAC_1_SQRT_2);
+        }
+    }
+
+    /// Split one sample into its low and high bands
+    #[inline]
+    pub fn split(&mut self, input: f32) -> (f32, f32) {
+        let low = self
+            .lowpass
+            .iter_mut()
+            .fold(input, |sample, stage| stage.process(sample));
+        let high = self
+            .highpass
+            .iter_mut()
+            .fold(input, |sample, stage| stage.process(sample));
+        (low, high)
+    }
+
+    /// Flush denormal state to zero, and reset both bands if either has
+    /// gone NaN or infinite. Returns whether they were reset.
+    pub fn sanitize(&mut self) -> bool {
+        let finite = self
+            .lowpass
+            .iter_mut()
+            .chain(&mut self.highpass)
+            .fold(true, |finite, stage| stage.sanitize_state() & finite);
+        if !finite {
+            self.reset();
+        }
+        !finite
+    }
+
+    /// Reset both bands
+    pub fn reset(&mut self) {
+        for stage in self.lowpass.iter_mut().chain(&mut self.highpass) {
+            stage.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FrequencyResponse;
+    use num_complex::Complex64;
+
+    #[test]
+    fn test_bands_sum_flat() {
+        let sample_rate = 48000.0;
+        let mut crossover = LinkwitzRiley::default();
+        crossover.set_frequency(sample_rate, 120.0);
+
+        let band = |stages: &[BiquadState], freq: f32| {
+            stages
+                .iter()
+                .fold(Complex64::new(1.0, 0.0), |response, stage| {
+                    response * stage.response(sample_rate, freq)
+                })
+        };
+        for freq in [20.0, 60.0, 120.0, 240.0, 1000.0, 10_000.0] {
+            let low = band(&crossover.lowpass, freq);
+            let high = band(&crossover.highpass, freq);
+            assert!(((low + high).norm() - 1.0).abs() < 1e-3, "{} Hz", freq);
+        }
+
+        // Both bands are 6 dB down at the crossover
+        let low_db = 20.0 * band(&crossover.lowpass, 120.0).norm().log10();
+        assert!((low_db + 6.02).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_nan_recovery() {
+        let mut crossover = LinkwitzRiley::default();
+        crossover.set_frequency(48000.0, 120.0);
+
+        let (low, high) = crossover.split(f32::NAN);
+        assert!(low.is_nan() && high.is_nan());
+        assert!(crossover.sanitize());
+        assert!(!crossover.sanitize());
+        for _ in 0..64 {
+            let (low, high) = crossover.split(0.5);
+            assert!(low.is_finite() && high.is_finite());
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::f64::consts::TAU;
+
+/// First-order high-pass for DC and subsonic removal, down to 1 Hz.
+///
+/// Written as the input minus a leaky integrator's estimate of its DC.
+/// The integrator runs in 64-bit, where a 1 Hz coefficient keeps its
+/// precision, and its state is the offset being removed.
+#[derive(Clone, Copy, Default)]
+pub struct DcBlocker {
+    /// Share of the input the estimate moves by each sample
+    coeff: f64,
+    dc: f64,
+}
+
+impl DcBlocker {
+    /// Set the -3 dB frequency
+    pub fn set_cutoff(&mut self, sample_rate: f32, freq: f32) {
+        self.coeff = 1.0 - (-TAU * freq as f64 / sample_rate as f64).exp();
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let input = input as f64;
+        self.dc += (input - self.dc) * self.coeff;
+        (input - self.dc) as f32
+    }
+
+    /// Offset currently being removed
+    pub fn dc(&self) -> f32 {
+        self.dc as f32
+    }
+
+    /// Forget the offset estimate if it has gone NaN or infinite. Returns
+    /// whether it was reset.
+    pub fn sanitize(&mut self) -> bool {
+        let reset = !self.dc.is_finite();
+        if reset {
+            self.reset();
+        }
+        reset
+    }
+
+    /// Forget the offset estimate
+    pub fn reset(&mut self) {
+        self.dc = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    #[test]
+    fn test_offset_is_removed_and_reported() {
+        let mut blocker = DcBlocker::default();
+        blocker.set_cutoff(SAMPLE_RATE, 5.0);
+
+        // Whole cycles of the tone average out, leaving the offset
+        let samples = (2.0 * SAMPLE_RATE) as usize;
+        let tail = SAMPLE_RATE as usize / 2;
+        let mut sum = 0.0;
+        for n in 0..samples {
+            let tone = 0.25 * (TAU * 100.0 * n as f32 / SAMPLE_RATE).sin();
+            let output = blocker.process(0.3 + tone);
+            if n >= samples - tail {
+                sum += output;
+            }
+        }
+        assert!((sum / tail as f32).abs() < 1e-3);
+        // The estimate still carries a little of the tone
+        assert!((blocker.dc() - 0.3).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_one_hz_cutoff_is_3db_down() {
+        let mut blocker = DcBlocker::default();
+        blocker.set_cutoff(SAMPLE_RATE, 1.0);
+
+        // Settle for a few seconds, then take the peak of the last cycle
+        let samples = (6.0 * SAMPLE_RATE) as usize;
+        let mut peak = 0.0f32;
+        for n in 0..samples {
+            let output = blocker.process((TAU * n as f32 / SAMPLE_RATE).sin());
+            if n >= samples - SAMPLE_RATE as usize {
+                peak = peak.max(output.abs());
+            }
+        }
+        assert!((20.0 * peak.log10() + 3.01).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_nan_recovery() {
+        let mut blocker = DcBlocker::default();
+        blocker.set_cutoff(SAMPLE_RATE, 5.0);
+
+        assert!(blocker.process(f32::NAN).is_nan());
+        assert!(blocker.sanitize());
+        assert!(!blocker.sanitize());
+        for _ in 0..SAMPLE_RATE as usize {
+            assert!(blocker.process(0.3).is_finite());
+        }
+        assert!((blocker.dc() - 0.3).abs() < 1e-3);
+    }
+}
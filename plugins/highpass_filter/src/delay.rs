@@ -0,0 +1,55 @@
+/// Integer-sample delay line, used to keep unfiltered channels aligned
+/// with the latency of the filtered ones
+#[derive(Default)]
+pub struct DelayLine {
+    buffer: Vec<f32>,
+    write_pos: usize,
+}
+
+impl DelayLine {
+    /// Create a delay line holding up to `max_delay` samples
+    pub fn new(max_delay: usize) -> Self {
+        Self {
+            buffer: vec![0.0; max_delay + 1],
+            write_pos: 0,
+        }
+    }
+
+    /// Push one sample and read the one from `delay` samples ago
+    #[inline]
+    pub fn process(&mut self, input: f32, delay: usize) -> f32 {
+        let len = self.buffer.len();
+        if len == 0 {
+            return input;
+        }
+
+        self.buffer[self.write_pos] = input;
+        let output = self.buffer[(self.write_pos + len - delay.min(len - 1)) % len];
+        self.write_pos = (self.write_pos + 1) % len;
+
+        output
+    }
+
+    /// Clear the delayed samples
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.write_pos = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impulse_is_delayed() {
+        let mut delay = DelayLine::new(8);
+
+        let response: Vec<f32> = (0..12)
+            .map(|n| delay.process(if n == 0 { 1.0 } else { 0.0 }, 5))
+            .collect();
+
+        assert_eq!(response.iter().position(|&x| x == 1.0), Some(5));
+        assert_eq!(response.iter().sum::<f32>(), 1.0);
+    }
+}
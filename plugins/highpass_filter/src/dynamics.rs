@@ -0,0 +1,153 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::envelope::EnvelopeFollower;
+use crate::filter::BiquadState;
+
+/// Lower edge of the detector band, keeping DC out of the level
+const DETECTOR_LOW_HZ: f32 = 20.0;
+
+/// Level of the band below the cutoff, driving the dynamic high-pass
+#[derive(Clone, Copy, Default)]
+pub struct DynamicDetector {
+    /// High-pass at the bottom of the band, then low-pass at the cutoff
+    bandpass: [BiquadState; 2],
+    envelope: EnvelopeFollower,
+    /// Cutoff the band was last set to
+    cutoff: f32,
+}
+
+impl DynamicDetector {
+    /// Set the attack and release times in milliseconds
+    pub fn set_times(&mut self, sample_rate: f32, attack_ms: f32, release_ms: f32) {
+        self.envelope.set_times(sample_rate, attack_ms, release_ms);
+    }
+
+    /// Move the top of the band, skipping the redesign if it has not moved
+    pub fn set_cutoff(&mut self, sample_rate: f32, cutoff: f32) {
+        if cutoff != self.cutoff {
+            let [highpass, lowpass] = &mut self.bandpass;
+            highpass.set_highpass(
+                sample_rate,
+                DETECTOR_LOW_HZ.min(0.5 * cutoff),
+                FRAC_1_SQRT_2,
+            );
+            lowpass.set_lowpass(sample_rate, cutoff, FRAC_1_SQRT_2);
+            self.cutoff = cutoff;
+        }
+    }
+
+    /// Detect one sample of the key signal and return the band level in dB
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let band = self
+            .bandpass
+            .iter_mut()
+            .fold(input, |sample, stage| stage.process(sample));
+        20.0 * self.envelope.process(band).max(1e-6).log10()
+    }
+
+    /// Flush denormal band state to zero, and reset the detector if the
+    /// band has gone NaN or infinite. Returns whether it was reset.
+    pub fn sanitize(&mut self) -> bool {
+        let finite = self
+            .bandpass
+            .iter_mut()
+            .fold(true, |finite, stage| stage.sanitize_state() & finite);
+        if !finite {
+            self.reset();
+        }
+        !finite
+    }
+
+    /// Clear the band and envelope state, keeping the band's design
+    pub fn reset(&mut self) {
+        for stage in &mut self.bandpass {
+            stage.reset();
+        }
+        self.envelope.reset();
+    }
+}
+
+/// Reduction of the low band in dB for a detected level, like a compressor
+/// with a hard knee, limited to `range_db`
+pub fn gain_reduction_db(level_db: f32, threshold_db: f32, ratio: f32, range_db: f32) -> f32 {
+    let over = (level_db - threshold_db).max(0.0);
+    (over * (1.0 - 1.0 / ratio)).min(range_db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{FilterChain, FilterSlope, FilterType, FrequencyResponse, PhaseMatch};
+    use num_complex::Complex64;
+    use std::f32::consts::TAU;
+
+    #[test]
+    fn test_detector_only_hears_the_low_band() {
+        let sample_rate = 48000.0;
+        let level = |freq: f32| {
+            let mut detector = DynamicDetector::default();
+            detector.set_times(sample_rate, 5.0, 100.0);
+            detector.set_cutoff(sample_rate, 150.0);
+            let mut level_db = 0.0;
+            for n in 0..sample_rate as usize / 2 {
+                level_db = detector.process((TAU * freq * n as f32 / sample_rate).sin());
+            }
+            level_db
+        };
+
+        assert!(level(60.0) > -2.0);
+        assert!(level(2000.0) < -40.0);
+        assert!(level(5.0) < -20.0);
+    }
+
+    #[test]
+    fn test_nan_recovery() {
+        let sample_rate = 48000.0;
+        let mut detector = DynamicDetector::default();
+        detector.set_times(sample_rate, 5.0, 100.0);
+        detector.set_cutoff(sample_rate, 150.0);
+
+        detector.process(f32::NAN);
+        assert!(detector.sanitize());
+        assert!(!detector.sanitize());
+        let mut level_db = 0.0;
+        for n in 0..sample_rate as usize / 2 {
+            level_db = detector.process((TAU * 60.0 * n as f32 / sample_rate).sin());
+        }
+        assert!(level_db > -2.0);
+    }
+
+    #[test]
+    fn test_partial_engagement_has_no_notch_at_cutoff() {
+        let sample_rate = 48000.0;
+        let cutoff = 150.0;
+        let mut wet = FilterChain::<f32>::default();
+        wet.update_coefficients(
+            sample_rate,
+            cutoff,
+            FRAC_1_SQRT_2,
+            0.0,
+            FilterType::HighPass,
+            FilterSlope::Slope24dB,
+        );
+        let mut matched = PhaseMatch::default();
+        matched.follow(&wet, sample_rate, sample_rate, FilterType::HighPass);
+
+        // Where the -3 dB wet signal, half a turn out, cancels the dry one
+        let engagement = 1.0 / (1.0 + FRAC_1_SQRT_2 as f64);
+        let w = wet.response(sample_rate, cutoff);
+        let blend_db = |d: Complex64| 20.0 * (d + (w - d) * engagement).norm().log10();
+
+        assert!(blend_db(Complex64::new(1.0, 0.0)) < -40.0);
+        assert!(blend_db(matched.response(sample_rate, cutoff)) > -3.0);
+    }
+
+    #[test]
+    fn test_gain_reduction_follows_ratio_and_range() {
+        assert_eq!(gain_reduction_db(-30.0, -24.0, 4.0, 24.0), 0.0);
+        assert_eq!(gain_reduction_db(-16.0, -24.0, 4.0, 24.0), 6.0);
+        assert_eq!(gain_reduction_db(0.0, -24.0, 100.0, 12.0), 12.0);
+        assert_eq!(gain_reduction_db(0.0, -24.0, 1.0, 24.0), 0.0);
+    }
+}
@@ -0,0 +1,246 @@
+use editor_common::{
+    draw_grid, param_slider, PlotAxes, BACKGROUND, MIN_HEIGHT, MIN_WIDTH, WINDOW_HEIGHT,
+    WINDOW_WIDTH,
+};
+use nih_plug::prelude::*;
+use nih_plug_egui::egui::{self, Color32, Pos2, Rect, Sense, Shape, Stroke, Vec2};
+use nih_plug_egui::resizable_window::ResizableWindow;
+use nih_plug_egui::{create_egui_editor, EguiState};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::learn::LearnExchange;
+use crate::spectrum::{Spectrum, MIN_DB};
+use crate::{from_filter_slope, ActiveResponse, EditorData, HighPassParams};
+
+/// Level range of the response curve
+const RESPONSE_MIN_DB: f32 = -48.0;
+const RESPONSE_MAX_DB: f32 = 24.0;
+
+const PRE_SPECTRUM: Color32 = Color32::from_rgb(80, 80, 90);
+const POST_SPECTRUM: Color32 = Color32::from_rgb(70, 140, 150);
+const MAIN_CURVE: Color32 = Color32::from_rgb(240, 160, 60);
+const SIDE_CURVE: Color32 = Color32::from_rgb(150, 120, 230);
+const WARNING: Color32 = Color32::from_rgb(230, 90, 70);
+const IN_PHASE: Color32 = Color32::from_rgb(90, 190, 110);
+
+pub fn default_state() -> Arc<EguiState> {
+    EguiState::from_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+}
+
+pub fn create(
+    params: Arc<HighPassParams>,
+    editor_data: Arc<EditorData>,
+    editor_state: Arc<EguiState>,
+) -> Option<Box<dyn Editor>> {
+    let window_state = editor_state.clone();
+
+    create_egui_editor(
+        editor_state,
+        Spectrum::default(),
+        |_, _| {},
+        move |egui_ctx, setter, spectrum| {
+            if let Ok(input) = editor_data.spectrum.try_lock() {
+                spectrum.update(&input);
+            }
+            let response = *editor_data.response.lock().unwrap();
+
+            ResizableWindow::new("highpass-filter")
+                .min_size(Vec2::new(MIN_WIDTH, MIN_HEIGHT))
+                .show(egui_ctx, window_state.as_ref(), |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        param_slider(ui, "Type", &params.filter_type, setter);
+                        param_slider(ui, "Cutoff", &params.cutoff, setter);
+                        param_slider(ui, "Q", &params.resonance, setter);
+                        param_slider(ui, "Slope", &params.slope, setter);
+                        param_slider(ui, "Gain", &params.gain, setter);
+                        param_slider(ui, "Alignment", &params.alignment, setter);
+                        param_slider(ui, "Engine", &params.engine, setter);
+                        param_slider(ui, "Quality", &params.quality, setter);
+                        param_slider(ui, "Character", &params.character, setter);
+                        param_slider(ui, "Drive", &params.drive, setter);
+                        param_slider(ui, "Phase", &params.phase_mode, setter);
+                        param_slider(ui, "Stereo", &params.stereo_mode, setter);
+                        param_slider(ui, "Dynamic", &params.dynamic, setter);
+                        param_slider(ui, "Threshold", &params.dyn_threshold, setter);
+                        param_slider(ui, "Ratio", &params.dyn_ratio, setter);
+                        param_slider(ui, "Attack", &params.dyn_attack, setter);
+                        param_slider(ui, "Release", &params.dyn_release, setter);
+                        param_slider(ui, "Range", &params.dyn_range, setter);
+                        if params.dynamic.value() {
+                            let gain_reduction =
+                                f32::from_bits(editor_data.gain_reduction.load(Ordering::Relaxed));
+                            ui.label(format!("GR {:.1} dB", -gain_reduction));
+                        }
+                        param_slider(ui, "Bass Mono", &params.bass_mono, setter);
+                        param_slider(ui, "Bass Freq", &params.bass_mono_freq, setter);
+                        param_slider(ui, "Bass Width", &params.bass_width, setter);
+                        param_slider(ui, "DC Mode", &params.dc_mode, setter);
+                        param_slider(ui, "DC Cutoff", &params.dc_cutoff, setter);
+                        for (channel, offset) in editor_data.dc_offsets.iter().enumerate() {
+                            let offset = f32::from_bits(offset.load(Ordering::Relaxed));
+                            if !offset.is_nan() {
+                                ui.label(format!("DC {}: {:+.4}", channel + 1, offset));
+                            }
+                        }
+                        param_slider(ui, "Mix", &params.mix, setter);
+                        param_slider(ui, "Phase-Matched Dry", &params.phase_matched_dry, setter);
+                        param_slider(ui, "Output", &params.output_gain, setter);
+                        param_slider(ui, "Bypass", &params.bypass, setter);
+
+                        let correlation =
+                            f32::from_bits(editor_data.correlation.load(Ordering::Relaxed));
+                        if !correlation.is_nan() {
+                            ui.label("Correlation");
+                            correlation_meter(ui, correlation);
+                        }
+
+                        let recoveries = editor_data.recoveries.load(Ordering::Relaxed);
+                        if recoveries > 0 {
+                            ui.colored_label(
+                                WARNING,
+                                format!("Recovered from {} invalid filter states", recoveries),
+                            );
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        learn_controls(ui, &params, setter, &editor_data.learn);
+                    });
+                    ui.add_space(4.0);
+
+                    draw_plot(ui, &params, setter, &response, spectrum);
+                });
+
+            // The spectrum keeps moving without any input
+            egui_ctx.request_repaint();
+        },
+    )
+}
+
+/// Learn button, its progress and the suggestion it came up with
+fn learn_controls(
+    ui: &mut egui::Ui,
+    params: &HighPassParams,
+    setter: &ParamSetter,
+    learn: &LearnExchange,
+) {
+    let listening =
+        learn.requested.load(Ordering::Relaxed) || learn.listening.load(Ordering::Relaxed);
+    if listening {
+        ui.add_enabled(false, egui::Button::new("Learn"));
+        ui.label("Listening...");
+        return;
+    }
+    if ui.button("Learn").clicked() {
+        learn.requested.store(true, Ordering::Relaxed);
+    }
+
+    let Some(result) = learn.result.lock().unwrap().clone() else {
+        ui.label("Play the track and learn a cutoff from its low end");
+        return;
+    };
+    ui.label(&result.explanation);
+    if let Some(suggestion) = result.suggestion {
+        if ui.button("Apply").clicked() {
+            setter.begin_set_parameter(&params.cutoff);
+            setter.set_parameter(&params.cutoff, suggestion.cutoff);
+            setter.end_set_parameter(&params.cutoff);
+            setter.begin_set_parameter(&params.slope);
+            setter.set_parameter(&params.slope, from_filter_slope(suggestion.slope));
+            setter.end_set_parameter(&params.slope);
+        }
+    }
+}
+
+/// Bar from the centre towards -1 (out of phase) or +1 (mono)
+fn correlation_meter(ui: &mut egui::Ui, correlation: f32) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(90.0, 14.0), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, BACKGROUND);
+
+    let centre = rect.center().x;
+    let end = centre + correlation.clamp(-1.0, 1.0) * 0.5 * rect.width();
+    let color = if correlation < 0.0 { WARNING } else { IN_PHASE };
+    painter.rect_filled(
+        Rect::from_x_y_ranges(centre.min(end)..=centre.max(end), rect.y_range()),
+        0.0,
+        color,
+    );
+    painter.line_segment(
+        [
+            Pos2::new(centre, rect.top()),
+            Pos2::new(centre, rect.bottom()),
+        ],
+        Stroke::new(1.0, Color32::GRAY),
+    );
+}
+
+/// Spectrum overlay, response curves and the cutoff/Q handle
+fn draw_plot(
+    ui: &mut egui::Ui,
+    params: &HighPassParams,
+    setter: &ParamSetter,
+    response: &ActiveResponse,
+    spectrum: &Spectrum,
+) {
+    let (rect, drag) = ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, BACKGROUND);
+
+    let axes = PlotAxes::new(rect, RESPONSE_MIN_DB, RESPONSE_MAX_DB);
+    let spectrum_axes = axes.with_db_range(MIN_DB, 0.0);
+    draw_grid(&painter, &axes, 12.0);
+
+    painter.add(Shape::line(
+        spectrum_axes.curve(|freq| spectrum.pre_db(freq)),
+        Stroke::new(1.0, PRE_SPECTRUM),
+    ));
+    painter.add(Shape::line(
+        spectrum_axes.curve(|freq| spectrum.post_db(freq)),
+        Stroke::new(1.0, POST_SPECTRUM),
+    ));
+
+    // Curves show the output after the mix and output gain
+    if response.split {
+        painter.add(Shape::line(
+            axes.curve(|freq| response.magnitude_db(1, freq)),
+            Stroke::new(2.0, SIDE_CURVE),
+        ));
+    }
+    painter.add(Shape::line(
+        axes.curve(|freq| response.magnitude_db(0, freq)),
+        Stroke::new(2.0, MAIN_CURVE),
+    ));
+
+    // Handle sits on the curve at the cutoff; drag sideways for cutoff,
+    // up and down for Q
+    let cutoff = params.cutoff.value();
+    let handle = Pos2::new(
+        axes.freq_to_x(cutoff),
+        axes.db_to_y(response.magnitude_db(0, cutoff)),
+    );
+    let hovered = drag
+        .hover_pos()
+        .is_some_and(|pos| pos.distance(handle) < 12.0);
+    let radius = if hovered || drag.dragged() { 8.0 } else { 6.0 };
+    painter.circle_filled(handle, radius, MAIN_CURVE);
+    painter.circle_stroke(handle, radius, Stroke::new(1.5, Color32::WHITE));
+
+    if drag.drag_started() {
+        setter.begin_set_parameter(&params.cutoff);
+        setter.begin_set_parameter(&params.resonance);
+    }
+    if drag.dragged() {
+        if let Some(pos) = drag.interact_pointer_pos() {
+            setter.set_parameter(&params.cutoff, axes.x_to_freq(pos.x));
+        }
+        let resonance = (params.resonance.unmodulated_normalized_value()
+            - drag.drag_delta().y / rect.height())
+        .clamp(0.0, 1.0);
+        setter.set_parameter_normalized(&params.resonance, resonance);
+    }
+    if drag.drag_stopped() {
+        setter.end_set_parameter(&params.resonance);
+        setter.end_set_parameter(&params.cutoff);
+    }
+}
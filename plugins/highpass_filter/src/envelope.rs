@@ -0,0 +1,78 @@
+/// Lowest level the follower responds to, mapped to zero modulation
+const FLOOR_DB: f32 = -60.0;
+
+/// Peak envelope follower with separate attack and release times
+#[derive(Clone, Copy, Default)]
+pub struct EnvelopeFollower {
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+}
+
+impl EnvelopeFollower {
+    /// Set the attack and release times in milliseconds
+    pub fn set_times(&mut self, sample_rate: f32, attack_ms: f32, release_ms: f32) {
+        self.attack_coeff = time_coefficient(sample_rate, attack_ms);
+        self.release_coeff = time_coefficient(sample_rate, release_ms);
+    }
+
+    /// Track one sample of the key signal and return the envelope
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let level = input.abs();
+        let coeff = if level > self.envelope {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.envelope = level + coeff * (self.envelope - level);
+        self.envelope
+    }
+
+    /// Envelope mapped from the -60..0 dBFS range to 0..1
+    pub fn normalized(&self) -> f32 {
+        let db = 20.0 * self.envelope.max(1e-6).log10();
+        ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0)
+    }
+
+    /// Clear the envelope
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+}
+
+/// One-pole coefficient reaching 1 - 1/e of a step in `time_ms`
+pub(crate) fn time_coefficient(sample_rate: f32, time_ms: f32) -> f32 {
+    let samples = time_ms * 0.001 * sample_rate;
+    if samples < 1.0 {
+        0.0
+    } else {
+        (-1.0 / samples).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attack_and_release_times() {
+        let sample_rate = 48000.0;
+        let mut follower = EnvelopeFollower::default();
+        follower.set_times(sample_rate, 10.0, 100.0);
+
+        // One time constant of a full-scale step reaches 1 - 1/e
+        let attack_samples = (0.01 * sample_rate) as usize;
+        let mut envelope = 0.0;
+        for _ in 0..attack_samples {
+            envelope = follower.process(1.0);
+        }
+        assert!((envelope - (1.0 - (-1.0f32).exp())).abs() < 0.01);
+        assert!(follower.normalized() > 0.9);
+
+        for _ in 0..(2.0 * sample_rate) as usize {
+            follower.process(0.0);
+        }
+        assert_eq!(follower.normalized(), 0.0);
+    }
+}
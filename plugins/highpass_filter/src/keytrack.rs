@@ -0,0 +1,179 @@
+/// Most held notes remembered for falling back to on release
+const MAX_HELD_NOTES: usize = 16;
+
+/// How overlapping notes move the tracked pitch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyMode {
+    /// Every new note glides and takes its own velocity
+    #[default]
+    LastNote,
+    /// Only notes played over a held one glide, and they keep the velocity
+    /// of the phrase; a detached note jumps straight to its pitch
+    Legato,
+}
+
+/// Monophonic note tracker following the most recent held note, with a
+/// constant-time glide between pitches
+#[derive(Clone, Copy, Default)]
+pub struct KeyTracker {
+    held: [u8; MAX_HELD_NOTES],
+    num_held: usize,
+    /// Current pitch in semitones, `None` until the first note
+    pitch: Option<f32>,
+    target: f32,
+    /// Pitch change per sample while gliding
+    step: f32,
+    /// Samples left until the pitch lands on the target
+    remaining: u32,
+    glide_samples: u32,
+    velocity: f32,
+}
+
+impl KeyTracker {
+    /// Set the time a glide takes, whatever the interval
+    pub fn set_glide(&mut self, sample_rate: f32, glide_ms: f32) {
+        self.glide_samples = (0.001 * glide_ms * sample_rate).round() as u32;
+    }
+
+    /// Start a note with a velocity in 0..1
+    pub fn note_on(&mut self, note: u8, velocity: f32, mode: KeyMode) {
+        let legato = self.num_held > 0;
+        self.remove(note);
+        if self.num_held == MAX_HELD_NOTES {
+            self.held.copy_within(1.., 0);
+            self.num_held -= 1;
+        }
+        self.held[self.num_held] = note;
+        self.num_held += 1;
+
+        match mode {
+            KeyMode::LastNote => {
+                self.velocity = velocity;
+                self.glide_to(note, true);
+            }
+            KeyMode::Legato => {
+                if !legato {
+                    self.velocity = velocity;
+                }
+                self.glide_to(note, legato);
+            }
+        }
+    }
+
+    /// Release a note, falling back to the most recent one still held.
+    /// The pitch stays put once no notes are held.
+    pub fn note_off(&mut self, note: u8) {
+        let was_top = self.num_held > 0 && self.held[self.num_held - 1] == note;
+        self.remove(note);
+        if was_top && self.num_held > 0 {
+            self.glide_to(self.held[self.num_held - 1], true);
+        }
+    }
+
+    /// Advance one sample and return the pitch in semitones, or `None`
+    /// before any note was played
+    #[inline]
+    pub fn next(&mut self) -> Option<f32> {
+        let pitch = self.pitch.as_mut()?;
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            *pitch = if self.remaining == 0 {
+                self.target
+            } else {
+                *pitch + self.step
+            };
+        }
+        Some(*pitch)
+    }
+
+    /// Velocity of the note or legato phrase being tracked
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    /// Whether the pitch is still on its way to a note
+    pub fn is_gliding(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Forget held notes and the tracked pitch
+    pub fn reset(&mut self) {
+        self.num_held = 0;
+        self.pitch = None;
+        self.remaining = 0;
+    }
+
+    fn glide_to(&mut self, note: u8, glide: bool) {
+        self.target = note as f32;
+        match self.pitch {
+            Some(pitch) if glide && self.glide_samples > 0 => {
+                self.step = (self.target - pitch) / self.glide_samples as f32;
+                self.remaining = self.glide_samples;
+            }
+            _ => {
+                self.pitch = Some(self.target);
+                self.remaining = 0;
+            }
+        }
+    }
+
+    fn remove(&mut self, note: u8) {
+        if let Some(index) = self.held[..self.num_held].iter().position(|&n| n == note) {
+            self.held.copy_within(index + 1..self.num_held, index);
+            self.num_held -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_note_glides_and_falls_back() {
+        let mut tracker = KeyTracker::default();
+        tracker.set_glide(1000.0, 10.0);
+        assert_eq!(tracker.next(), None);
+
+        // The first note has nothing to glide from
+        tracker.note_on(60, 1.0, KeyMode::LastNote);
+        assert_eq!(tracker.next(), Some(60.0));
+
+        tracker.note_on(72, 0.5, KeyMode::LastNote);
+        assert_eq!(tracker.velocity(), 0.5);
+        let pitches: Vec<f32> = (0..10).filter_map(|_| tracker.next()).collect();
+        assert!((pitches[4] - 66.0).abs() < 1e-4);
+        assert_eq!(pitches[9], 72.0);
+        assert!(!tracker.is_gliding());
+
+        // Releasing the top note goes back to the one still held, and the
+        // pitch holds once everything is released
+        tracker.note_off(72);
+        assert!(tracker.is_gliding());
+        for _ in 0..10 {
+            tracker.next();
+        }
+        assert_eq!(tracker.next(), Some(60.0));
+        tracker.note_off(60);
+        assert_eq!(tracker.next(), Some(60.0));
+    }
+
+    #[test]
+    fn test_legato_only_glides_overlapping_notes() {
+        let mut tracker = KeyTracker::default();
+        tracker.set_glide(1000.0, 10.0);
+
+        tracker.note_on(60, 0.8, KeyMode::Legato);
+        tracker.note_off(60);
+        // Detached notes jump and take their own velocity
+        tracker.note_on(67, 0.4, KeyMode::Legato);
+        assert_eq!(tracker.next(), Some(67.0));
+        assert_eq!(tracker.velocity(), 0.4);
+
+        // An overlapping note glides and keeps the phrase velocity
+        tracker.note_on(79, 1.0, KeyMode::Legato);
+        assert_eq!(tracker.velocity(), 0.4);
+        assert!(tracker.next().is_some_and(|pitch| pitch < 79.0));
+        assert!(tracker.is_gliding());
+    }
+}
@@ -0,0 +1,307 @@
+use num_complex::Complex32;
+use realfft::RealFftPlanner;
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+use crate::filter::FilterSlope;
+
+/// Length of input the analysis listens to
+pub const LEARN_SECONDS: f32 = 4.0;
+
+/// Analysis window length, long enough to resolve low fundamentals
+const FFT_SIZE: usize = 16384;
+
+/// Quietest average input level the analysis works with
+const SILENCE_DB: f32 = -60.0;
+
+/// Lowest frequency counted as a musical fundamental rather than rumble
+const MIN_FUNDAMENTAL: f32 = 30.0;
+
+/// How far below the loudest peak a fundamental may sit and still count
+const RELEVANCE_DB: f32 = 30.0;
+
+/// How far a peak has to rise above the half octave below it
+const PROMINENCE_DB: f32 = 6.0;
+
+/// Suggested cutoff relative to the fundamental. An octave below leaves
+/// the fundamental untouched by any of the slopes.
+const CUTOFF_RATIO: f32 = 0.5;
+
+/// Cutoff and slope proposed from the analyzed input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Suggestion {
+    pub cutoff: f32,
+    pub slope: FilterSlope,
+    /// Lowest fundamental found
+    pub fundamental: f32,
+    /// Energy below the cutoff relative to the total, in dB
+    pub rumble_db: f32,
+}
+
+/// Outcome of one analysis, with a short explanation for the editor
+#[derive(Debug, Clone, Default)]
+pub struct LearnResult {
+    pub suggestion: Option<Suggestion>,
+    pub explanation: String,
+}
+
+/// Hand-off between the editor, the audio thread and the analysis task
+#[derive(Default)]
+pub struct LearnExchange {
+    /// Set by the editor, taken by the audio thread when it starts listening
+    pub requested: AtomicBool,
+    /// Whether the audio thread is recording input
+    pub listening: AtomicBool,
+    /// Finished recording for the analysis task, swapped in by the audio
+    /// thread so neither side allocates
+    pub recording: Mutex<Vec<f32>>,
+    pub result: Mutex<Option<LearnResult>>,
+}
+
+/// Samples in a full recording at `sample_rate`
+pub fn recording_len(sample_rate: f32) -> usize {
+    (LEARN_SECONDS * sample_rate) as usize
+}
+
+/// Audio thread side of the learning: fills a preallocated buffer
+#[derive(Default)]
+pub struct Recorder {
+    buffer: Vec<f32>,
+    len: usize,
+    active: bool,
+}
+
+impl Recorder {
+    /// Allocate room for a full recording at `sample_rate`
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            buffer: vec![0.0; recording_len(sample_rate)],
+            len: 0,
+            active: false,
+        }
+    }
+
+    /// Start a new recording
+    pub fn start(&mut self) {
+        self.len = 0;
+        self.active = true;
+    }
+
+    /// Whether a recording is in progress
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Record one sample, returning whether the recording is full
+    #[inline]
+    pub fn push(&mut self, sample: f32) -> bool {
+        if self.len < self.buffer.len() {
+            self.buffer[self.len] = sample;
+            self.len += 1;
+        }
+        self.len == self.buffer.len()
+    }
+
+    /// Hand a full recording over by swapping it with `other`, which must
+    /// be the same size, and stop recording
+    pub fn swap_into(&mut self, other: &mut Vec<f32>) {
+        std::mem::swap(&mut self.buffer, other);
+        self.active = false;
+    }
+
+    /// Abandon the recording in progress
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+}
+
+/// Find the lowest musically relevant fundamental in `samples`, and
+/// suggest a cutoff below it with a slope matching the energy underneath
+pub fn analyze(samples: &[f32], sample_rate: f32) -> LearnResult {
+    let mean_square = samples.iter().map(|x| x * x).sum::<f32>() / samples.len().max(1) as f32;
+    if 10.0 * mean_square.max(1e-20).log10() < SILENCE_DB {
+        return LearnResult {
+            suggestion: None,
+            explanation: "Input was silent; play the track while learning".to_string(),
+        };
+    }
+
+    let power = average_power(samples);
+    let bin_hz = sample_rate / FFT_SIZE as f32;
+    let db: Vec<f32> = power.iter().map(|p| 10.0 * p.max(1e-20).log10()).collect();
+
+    let first_bin = (MIN_FUNDAMENTAL / bin_hz).ceil().max(2.0) as usize;
+    let last_bin = ((20_000.0f32.min(0.45 * sample_rate)) / bin_hz) as usize;
+    let loudest = db[first_bin..=last_bin]
+        .iter()
+        .fold(f32::NEG_INFINITY, |max, &x| max.max(x));
+
+    let is_fundamental = |i: usize| {
+        let floor_bin = ((i as f32 * FRAC_1_SQRT_2) as usize).max(1);
+        let floor = db[floor_bin..i]
+            .iter()
+            .fold(f32::INFINITY, |min, &x| min.min(x));
+        db[i] > db[i - 1]
+            && db[i] > db[i + 1]
+            && db[i] >= db[i - 2]
+            && db[i] >= db[i + 2]
+            && db[i] > loudest - RELEVANCE_DB
+            && db[i] - floor >= PROMINENCE_DB
+    };
+    let Some(peak) = (first_bin..=last_bin).find(|&i| is_fundamental(i)) else {
+        return LearnResult {
+            suggestion: None,
+            explanation: "No clear fundamental found; the input may be noise".to_string(),
+        };
+    };
+
+    // Parabolic interpolation between the neighbouring bins
+    let (left, centre, right) = (db[peak - 1], db[peak], db[peak + 1]);
+    let offset = 0.5 * (left - right) / (left - 2.0 * centre + right);
+    let fundamental = (peak as f32 + offset) * bin_hz;
+    let cutoff = (fundamental * CUTOFF_RATIO).clamp(20.0, 20_000.0);
+
+    // DC is left out, it says nothing about what the track needs
+    let cutoff_bin = (cutoff / bin_hz) as usize;
+    let total: f32 = power[1..].iter().sum();
+    let below: f32 = power[1..cutoff_bin.max(1)].iter().sum();
+    let rumble_db = 10.0 * (below / total).max(1e-12).log10();
+
+    let (slope, reason) = if rumble_db > -20.0 {
+        (
+            FilterSlope::Slope48dB,
+            "strong rumble underneath calls for a steep slope",
+        )
+    } else if rumble_db > -40.0 {
+        (
+            FilterSlope::Slope24dB,
+            "some rumble underneath calls for a firm slope",
+        )
+    } else {
+        (
+            FilterSlope::Slope12dB,
+            "little is underneath, so a gentle slope is enough",
+        )
+    };
+    let explanation = format!(
+        "Lowest fundamental near {:.0} Hz. Energy below {:.0} Hz is {:.0} dB under the total; \
+         {}: {} dB/oct.",
+        fundamental,
+        cutoff,
+        -rumble_db,
+        reason,
+        6 * slope.order()
+    );
+
+    LearnResult {
+        suggestion: Some(Suggestion {
+            cutoff,
+            slope,
+            fundamental,
+            rumble_db,
+        }),
+        explanation,
+    }
+}
+
+/// Hann-windowed power spectrum averaged over half-overlapping frames
+fn average_power(samples: &[f32]) -> Vec<f32> {
+    let fft = RealFftPlanner::<f32>::new().plan_fft_forward(FFT_SIZE);
+    let window: Vec<f32> = (0..FFT_SIZE)
+        .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / FFT_SIZE as f32).cos())
+        .collect();
+    let mut input = fft.make_input_vec();
+    let mut output: Vec<Complex32> = fft.make_output_vec();
+    let mut power = vec![0.0; output.len()];
+
+    // A recording shorter than one frame is zero-padded
+    let num_frames = samples.len().saturating_sub(FFT_SIZE) / (FFT_SIZE / 2) + 1;
+    for frame in 0..num_frames {
+        let start = frame * FFT_SIZE / 2;
+        for (n, (x, w)) in input.iter_mut().zip(&window).enumerate() {
+            *x = samples.get(start + n).copied().unwrap_or(0.0) * w;
+        }
+        fft.process(&mut input, &mut output)
+            .expect("FFT processing failed");
+        for (p, bin) in power.iter_mut().zip(&output) {
+            *p += bin.norm_sqr() / num_frames as f32;
+        }
+    }
+    power
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn render(partials: &[(f32, f32)]) -> Vec<f32> {
+        (0..(LEARN_SECONDS * SAMPLE_RATE) as usize)
+            .map(|n| {
+                let t = n as f32 / SAMPLE_RATE;
+                partials
+                    .iter()
+                    .map(|&(freq, amplitude)| amplitude * (TAU * freq * t).sin())
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stopped_recording_starts_over() {
+        let mut recorder = Recorder::new(SAMPLE_RATE);
+        let len = recording_len(SAMPLE_RATE);
+        recorder.start();
+        for _ in 0..len / 2 {
+            assert!(!recorder.push(1.0));
+        }
+        recorder.stop();
+        assert!(!recorder.is_active());
+
+        // The next recording fills the whole buffer again
+        recorder.start();
+        assert!(recorder.is_active());
+        for _ in 1..len {
+            assert!(!recorder.push(0.5));
+        }
+        assert!(recorder.push(0.5));
+
+        let mut recording = vec![0.0; len];
+        recorder.swap_into(&mut recording);
+        assert!(recording.iter().all(|&x| x == 0.5));
+        assert!(!recorder.is_active());
+    }
+
+    #[test]
+    fn test_bass_over_rumble() {
+        // E1 bass with harmonics over a loud 12 Hz rumble
+        let samples = render(&[(12.0, 0.3), (41.2, 0.4), (82.4, 0.2), (123.6, 0.1)]);
+        let suggestion = analyze(&samples, SAMPLE_RATE).suggestion.unwrap();
+
+        assert!((suggestion.fundamental - 41.2).abs() < 1.0);
+        assert!((suggestion.cutoff - 20.6).abs() < 1.0);
+        assert_eq!(suggestion.slope, FilterSlope::Slope48dB);
+    }
+
+    #[test]
+    fn test_clean_tone_gets_gentle_slope() {
+        let samples = render(&[(220.0, 0.5), (440.0, 0.25)]);
+        let result = analyze(&samples, SAMPLE_RATE);
+        let suggestion = result.suggestion.unwrap();
+
+        assert!((suggestion.fundamental - 220.0).abs() < 1.0);
+        assert!((suggestion.cutoff - 110.0).abs() < 1.0);
+        assert_eq!(suggestion.slope, FilterSlope::Slope12dB);
+        assert!(result.explanation.contains("220 Hz"));
+    }
+
+    #[test]
+    fn test_silence_has_no_suggestion() {
+        let result = analyze(&vec![0.0; 48000], SAMPLE_RATE);
+        assert!(result.suggestion.is_none());
+        assert!(!result.explanation.is_empty());
+    }
+}
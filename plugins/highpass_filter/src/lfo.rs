@@ -0,0 +1,133 @@
+use std::f32::consts::TAU;
+
+/// LFO waveform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+    SampleAndHold,
+}
+
+/// Low-frequency oscillator with a bipolar output
+#[derive(Clone, Copy)]
+pub struct Lfo {
+    /// Position in the cycle, 0..1
+    phase: f32,
+    /// Current sample-and-hold value
+    held: f32,
+    rng_state: u32,
+}
+
+impl Default for Lfo {
+    fn default() -> Self {
+        Self {
+            phase: 0.0,
+            held: 0.0,
+            rng_state: 0x9e37_79b9,
+        }
+    }
+}
+
+impl Lfo {
+    /// Position in the cycle, 0..1
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// Jump to a position in the cycle. Landing just past the start of a
+    /// cycle counts as a wrap, so resyncing never skips a held value.
+    pub fn set_phase(&mut self, phase: f32) {
+        let phase = phase.rem_euclid(1.0);
+        if self.phase - phase > 0.5 {
+            self.held = self.next_random();
+        }
+        self.phase = phase;
+    }
+
+    /// Advance by `increment` cycles, drawing a new held value on each wrap
+    #[inline]
+    pub fn advance(&mut self, increment: f32) {
+        self.phase += increment;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.held = self.next_random();
+        }
+    }
+
+    /// Output in -1..1 at the current phase
+    #[inline]
+    pub fn value(&self, shape: LfoShape) -> f32 {
+        let phase = self.phase;
+        match shape {
+            LfoShape::Sine => (TAU * phase).sin(),
+            LfoShape::Triangle => 4.0 * ((phase + 0.75).fract() - 0.5).abs() - 1.0,
+            LfoShape::Saw => 2.0 * phase - 1.0,
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::SampleAndHold => self.held,
+        }
+    }
+
+    /// Xorshift noise in -1..1
+    fn next_random(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+        self.rng_state as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shapes_are_bipolar_and_periodic() {
+        let shapes = [
+            LfoShape::Sine,
+            LfoShape::Triangle,
+            LfoShape::Saw,
+            LfoShape::Square,
+        ];
+        for shape in shapes {
+            let mut lfo = Lfo::default();
+            let cycle: Vec<f32> = (0..64)
+                .map(|_| {
+                    let value = lfo.value(shape);
+                    lfo.advance(1.0 / 64.0);
+                    value
+                })
+                .collect();
+
+            let min = cycle.iter().copied().fold(f32::MAX, f32::min);
+            let max = cycle.iter().copied().fold(f32::MIN, f32::max);
+            assert!((-1.0..-0.95).contains(&min), "{:?} minimum {}", shape, min);
+            assert!((0.95..=1.0).contains(&max), "{:?} maximum {}", shape, max);
+            assert!((lfo.value(shape) - cycle[0]).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_sample_and_hold_steps_once_per_cycle() {
+        let mut lfo = Lfo::default();
+        let mut values = Vec::new();
+        for _ in 0..400 {
+            lfo.advance(0.01);
+            let value = lfo.value(LfoShape::SampleAndHold);
+            if values.last() != Some(&value) {
+                values.push(value);
+            }
+        }
+
+        assert_eq!(values.len(), 4);
+        assert!(values.iter().all(|x| (-1.0..=1.0).contains(x)));
+    }
+}
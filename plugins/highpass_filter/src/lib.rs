@@ -1,8 +1,41 @@
 use nih_plug::prelude::*;
-use std::sync::Arc;
+use nih_plug_egui::EguiState;
+use num_complex::Complex64;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
 
-mod filter;
-use filter::FilterChain;
+mod character;
+mod correlation;
+mod crossfade;
+mod crossover;
+mod dc_blocker;
+mod delay;
+mod dynamics;
+mod editor;
+mod envelope;
+mod keytrack;
+mod learn;
+mod lfo;
+mod linear_phase;
+mod oversampling;
+mod spectrum;
+use character::{CharacterLanes, CharacterModel};
+use correlation::CorrelationMeter;
+use crossfade::Crossfade;
+use crossover::LinkwitzRiley;
+use dc_blocker::DcBlocker;
+use delay::DelayLine;
+use dynamics::DynamicDetector;
+use envelope::EnvelopeFollower;
+use filter::{FilterChain, FrequencyResponse, PhaseMatch};
+use filter_dsp::{filter, lanes};
+use keytrack::KeyTracker;
+use lanes::{FilterChainLanes, PhaseMatchLanes};
+use learn::{LearnExchange, Recorder};
+use lfo::Lfo;
+use linear_phase::{Kernel, LinearPhaseFilter};
+use oversampling::Oversampler;
+use spectrum::SpectrumInput;
 
 /// Filter slope options
 #[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -20,11 +53,320 @@ pub enum FilterSlope {
     #[id = "24db"]
     #[name = "24 dB/oct"]
     Slope24dB,
+    #[id = "36db"]
+    #[name = "36 dB/oct"]
+    Slope36dB,
+    #[id = "48db"]
+    #[name = "48 dB/oct"]
+    Slope48dB,
+    #[id = "72db"]
+    #[name = "72 dB/oct"]
+    Slope72dB,
+    #[id = "96db"]
+    #[name = "96 dB/oct"]
+    Slope96dB,
+}
+
+/// Filter response types
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterType {
+    #[id = "highpass"]
+    #[name = "High-Pass"]
+    #[default]
+    HighPass,
+    #[id = "lowpass"]
+    #[name = "Low-Pass"]
+    LowPass,
+    #[id = "bandpass"]
+    #[name = "Band-Pass"]
+    BandPass,
+    #[id = "notch"]
+    #[name = "Notch"]
+    Notch,
+    #[id = "peak"]
+    #[name = "Peak"]
+    Peak,
+    #[id = "lowshelf"]
+    #[name = "Low Shelf"]
+    LowShelf,
+    #[id = "highshelf"]
+    #[name = "High Shelf"]
+    HighShelf,
+    #[id = "allpass"]
+    #[name = "All-Pass"]
+    AllPass,
+}
+
+/// Pole alignment of the cascaded stages
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterAlignment {
+    #[id = "butterworth"]
+    #[name = "Butterworth"]
+    #[default]
+    Butterworth,
+    #[id = "linkwitz_riley"]
+    #[name = "Linkwitz-Riley"]
+    LinkwitzRiley,
+    #[id = "bessel"]
+    #[name = "Bessel"]
+    Bessel,
+    #[id = "chebyshev"]
+    #[name = "Chebyshev I"]
+    Chebyshev,
+}
+
+/// Topology of the minimum-phase filter
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterEngine {
+    #[id = "biquad"]
+    #[name = "Biquad"]
+    #[default]
+    Biquad,
+    #[id = "svf"]
+    #[name = "SVF (ZDF)"]
+    Svf,
+}
+
+/// Precision of the minimum-phase filter's coefficient math and state
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quality {
+    #[id = "standard"]
+    #[name = "Standard (32-bit)"]
+    #[default]
+    Standard,
+    #[id = "high"]
+    #[name = "High (64-bit)"]
+    High,
+}
+
+/// Analog-modelled nonlinear high-pass replacing the clean filter
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Character {
+    #[id = "clean"]
+    #[name = "Clean"]
+    #[default]
+    Clean,
+    #[id = "sallen_key"]
+    #[name = "MS-20 (Sallen-Key)"]
+    SallenKey,
+    #[id = "ladder"]
+    #[name = "Transistor Ladder"]
+    Ladder,
+    #[id = "diode"]
+    #[name = "Diode Feedback"]
+    Diode,
+}
+
+/// Phase response of the filter
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PhaseMode {
+    #[id = "minimum"]
+    #[name = "Minimum Phase"]
+    #[default]
+    Minimum,
+    #[id = "linear"]
+    #[name = "Linear Phase"]
+    Linear,
+}
+
+/// Oversampling factor of the minimum-phase filter
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Oversampling {
+    #[id = "1x"]
+    #[name = "Off"]
+    #[default]
+    Off,
+    #[id = "2x"]
+    #[name = "2x"]
+    X2,
+    #[id = "4x"]
+    #[name = "4x"]
+    X4,
+    #[id = "8x"]
+    #[name = "8x"]
+    X8,
+}
+
+impl Oversampling {
+    fn factor(&self) -> usize {
+        match self {
+            Oversampling::Off => 1,
+            Oversampling::X2 => 2,
+            Oversampling::X4 => 4,
+            Oversampling::X8 => 8,
+        }
+    }
+}
+
+/// How a stereo pair is routed through the filters
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StereoMode {
+    #[id = "linked"]
+    #[name = "Linked L/R"]
+    #[default]
+    Linked,
+    #[id = "mid_side"]
+    #[name = "Mid/Side"]
+    MidSide,
+    #[id = "mid"]
+    #[name = "Mid Only"]
+    MidOnly,
+    #[id = "side"]
+    #[name = "Side Only"]
+    SideOnly,
+    #[id = "unlinked"]
+    #[name = "Unlinked L/R"]
+    Unlinked,
+}
+
+impl StereoMode {
+    /// Whether the pair is encoded to mid/side around the filters
+    fn is_mid_side(&self) -> bool {
+        matches!(
+            self,
+            StereoMode::MidSide | StereoMode::MidOnly | StereoMode::SideOnly
+        )
+    }
+
+    /// Signal of the pair that passes unfiltered, if any
+    fn dry_channel(&self) -> Option<usize> {
+        match self {
+            StereoMode::MidOnly => Some(1),
+            StereoMode::SideOnly => Some(0),
+            _ => None,
+        }
+    }
+}
+
+/// Signal the envelope follower listens to
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnvelopeSource {
+    #[id = "main"]
+    #[name = "Main Input"]
+    #[default]
+    Main,
+    #[id = "sidechain"]
+    #[name = "Sidechain"]
+    Sidechain,
+}
+
+/// Which way the envelope moves the cutoff as the level rises
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnvelopeDirection {
+    #[id = "up"]
+    #[name = "Up"]
+    #[default]
+    Up,
+    #[id = "down"]
+    #[name = "Down"]
+    Down,
+}
+
+/// LFO waveform
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LfoShape {
+    #[id = "sine"]
+    #[name = "Sine"]
+    #[default]
+    Sine,
+    #[id = "triangle"]
+    #[name = "Triangle"]
+    Triangle,
+    #[id = "saw"]
+    #[name = "Saw"]
+    Saw,
+    #[id = "square"]
+    #[name = "Square"]
+    Square,
+    #[id = "sample_hold"]
+    #[name = "Sample & Hold"]
+    SampleAndHold,
+}
+
+/// Tempo-synced LFO cycle length
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LfoDivision {
+    #[id = "4_1"]
+    #[name = "4 Bars"]
+    FourBars,
+    #[id = "2_1"]
+    #[name = "2 Bars"]
+    TwoBars,
+    #[id = "1_1"]
+    #[name = "1/1"]
+    Whole,
+    #[id = "1_2"]
+    #[name = "1/2"]
+    Half,
+    #[id = "1_4"]
+    #[name = "1/4"]
+    #[default]
+    Quarter,
+    #[id = "1_4d"]
+    #[name = "1/4 Dotted"]
+    QuarterDotted,
+    #[id = "1_4t"]
+    #[name = "1/4 Triplet"]
+    QuarterTriplet,
+    #[id = "1_8"]
+    #[name = "1/8"]
+    Eighth,
+    #[id = "1_8d"]
+    #[name = "1/8 Dotted"]
+    EighthDotted,
+    #[id = "1_8t"]
+    #[name = "1/8 Triplet"]
+    EighthTriplet,
+    #[id = "1_16"]
+    #[name = "1/16"]
+    Sixteenth,
+}
+
+/// Note priority of the key tracking
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyMode {
+    #[id = "last_note"]
+    #[name = "Last Note"]
+    #[default]
+    LastNote,
+    #[id = "legato"]
+    #[name = "Mono Legato"]
+    Legato,
+}
+
+impl LfoDivision {
+    /// Cycle length in quarter-note beats
+    fn beats(&self) -> f64 {
+        match self {
+            LfoDivision::FourBars => 16.0,
+            LfoDivision::TwoBars => 8.0,
+            LfoDivision::Whole => 4.0,
+            LfoDivision::Half => 2.0,
+            LfoDivision::Quarter => 1.0,
+            LfoDivision::QuarterDotted => 1.5,
+            LfoDivision::QuarterTriplet => 2.0 / 3.0,
+            LfoDivision::Eighth => 0.5,
+            LfoDivision::EighthDotted => 0.75,
+            LfoDivision::EighthTriplet => 1.0 / 3.0,
+            LfoDivision::Sixteenth => 0.25,
+        }
+    }
 }
 
 /// Plugin parameters
 #[derive(Params)]
 struct HighPassParams {
+    #[persist = "editor-state"]
+    pub editor_state: Arc<EguiState>,
+
+    /// Fades over to the latency-compensated dry signal instead of letting
+    /// the host cut the plugin out
+    #[id = "bypass"]
+    pub bypass: BoolParam,
+
+    #[id = "type"]
+    pub filter_type: EnumParam<FilterType>,
+
     #[id = "cutoff"]
     pub cutoff: FloatParam,
 
@@ -33,11 +375,182 @@ struct HighPassParams {
 
     #[id = "slope"]
     pub slope: EnumParam<FilterSlope>,
+
+    #[id = "gain"]
+    pub gain: FloatParam,
+
+    #[id = "alignment"]
+    pub alignment: EnumParam<FilterAlignment>,
+
+    #[id = "ripple"]
+    pub ripple: FloatParam,
+
+    /// The SVF engine stays clean under fast cutoff sweeps and follows
+    /// modulation every sample instead of every few
+    #[id = "engine"]
+    pub engine: EnumParam<FilterEngine>,
+
+    /// Runs the filter in 64-bit internally, for low cutoffs at high
+    /// sample rates; audio in and out stays 32-bit
+    #[id = "quality"]
+    pub quality: EnumParam<Quality>,
+
+    /// Nonlinear models are always high-passes with their own slope, and
+    /// only run in minimum-phase mode
+    #[id = "character"]
+    pub character: EnumParam<Character>,
+
+    #[id = "drive"]
+    pub drive: FloatParam,
+
+    #[id = "self_oscillation"]
+    pub self_oscillation: BoolParam,
+
+    #[id = "phase"]
+    pub phase_mode: EnumParam<PhaseMode>,
+
+    #[id = "oversampling"]
+    pub oversampling: EnumParam<Oversampling>,
+
+    #[id = "exclude_lfe"]
+    pub exclude_lfe: BoolParam,
+
+    #[id = "stereo_mode"]
+    pub stereo_mode: EnumParam<StereoMode>,
+
+    /// Second filter: the Side signal in mid/side modes, Right when unlinked
+    #[id = "side_cutoff"]
+    pub side_cutoff: FloatParam,
+
+    #[id = "side_resonance"]
+    pub side_resonance: FloatParam,
+
+    #[id = "side_slope"]
+    pub side_slope: EnumParam<FilterSlope>,
+
+    /// Cutoff modulation range in octaves at full envelope level. The
+    /// linear-phase kernel is not modulated.
+    #[id = "env_depth"]
+    pub env_depth: FloatParam,
+
+    #[id = "env_direction"]
+    pub env_direction: EnumParam<EnvelopeDirection>,
+
+    #[id = "env_attack"]
+    pub env_attack: FloatParam,
+
+    #[id = "env_release"]
+    pub env_release: FloatParam,
+
+    #[id = "env_source"]
+    pub env_source: EnumParam<EnvelopeSource>,
+
+    /// Cutoff modulation range in octaves either side of the cutoff. Like
+    /// the envelope, it leaves the linear-phase kernel alone.
+    #[id = "lfo_depth"]
+    pub lfo_depth: FloatParam,
+
+    #[id = "lfo_shape"]
+    pub lfo_shape: EnumParam<LfoShape>,
+
+    #[id = "lfo_sync"]
+    pub lfo_sync: BoolParam,
+
+    #[id = "lfo_rate"]
+    pub lfo_rate: FloatParam,
+
+    #[id = "lfo_division"]
+    pub lfo_division: EnumParam<LfoDivision>,
+
+    /// Phase offset of the right (or side) LFO
+    #[id = "lfo_stereo"]
+    pub lfo_stereo: FloatParam,
+
+    /// Share of the note's distance from the reference note the cutoff
+    /// follows; at 100% it moves an octave per octave played. Like the
+    /// envelope, it leaves the linear-phase kernel alone.
+    #[id = "key_track"]
+    pub key_track: FloatParam,
+
+    #[id = "key_reference"]
+    pub key_reference: IntParam,
+
+    #[id = "key_glide"]
+    pub key_glide: FloatParam,
+
+    /// Cutoff raise in octaves at full note velocity
+    #[id = "key_velocity"]
+    pub key_velocity: FloatParam,
+
+    #[id = "key_mode"]
+    pub key_mode: EnumParam<KeyMode>,
+
+    /// Engages the filter only while the band below the cutoff is above the
+    /// threshold, keyed like the envelope. Fully engaged, the filter reaches
+    /// its mix setting. The dry signal is phase-matched while it is on, as
+    /// a partial engagement is a partial mix.
+    #[id = "dynamic"]
+    pub dynamic: BoolParam,
+
+    #[id = "dyn_threshold"]
+    pub dyn_threshold: FloatParam,
+
+    #[id = "dyn_ratio"]
+    pub dyn_ratio: FloatParam,
+
+    #[id = "dyn_attack"]
+    pub dyn_attack: FloatParam,
+
+    #[id = "dyn_release"]
+    pub dyn_release: FloatParam,
+
+    /// Most the band below the cutoff is reduced by
+    #[id = "dyn_range"]
+    pub dyn_range: FloatParam,
+
+    /// Sums a stereo pair to mono below the crossover frequency, leaving
+    /// the highs as they are
+    #[id = "bass_mono"]
+    pub bass_mono: BoolParam,
+
+    #[id = "bass_mono_freq"]
+    pub bass_mono_freq: FloatParam,
+
+    /// Stereo width of the band below the crossover, 0% for mono
+    #[id = "bass_width"]
+    pub bass_width: FloatParam,
+
+    /// Replaces the filter with a first-order DC/subsonic high-pass that
+    /// adds no latency, reaching below the filter's cutoff range
+    #[id = "dc_mode"]
+    pub dc_mode: BoolParam,
+
+    #[id = "dc_cutoff"]
+    pub dc_cutoff: FloatParam,
+
+    /// Blend of the filtered signal against the latency-compensated dry one
+    #[id = "mix"]
+    pub mix: FloatParam,
+
+    /// Runs the dry signal through all-passes following the filter's phase,
+    /// so a partial mix has no notch at the cutoff. Only minimum-phase
+    /// high-pass and low-pass filters need it.
+    #[id = "phase_matched_dry"]
+    pub phase_matched_dry: BoolParam,
+
+    #[id = "output_gain"]
+    pub output_gain: FloatParam,
 }
 
 impl Default for HighPassParams {
     fn default() -> Self {
         Self {
+            editor_state: editor::default_state(),
+
+            bypass: BoolParam::new("Bypass", false).make_bypass(),
+
+            filter_type: EnumParam::new("Type", FilterType::HighPass),
+
             cutoff: FloatParam::new(
                 "Cutoff",
                 200.0,
@@ -65,15 +578,760 @@ impl Default for HighPassParams {
             .with_smoother(SmoothingStyle::Linear(50.0)),
 
             slope: EnumParam::new("Slope", FilterSlope::Slope12dB),
+
+            gain: FloatParam::new(
+                "Gain",
+                0.0,
+                FloatRange::Linear {
+                    min: -24.0,
+                    max: 24.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1))
+            .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            alignment: EnumParam::new("Alignment", FilterAlignment::Butterworth),
+
+            ripple: FloatParam::new(
+                "Ripple",
+                0.5,
+                FloatRange::Skewed {
+                    min: 0.01,
+                    max: 3.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            engine: EnumParam::new("Engine", FilterEngine::Biquad),
+
+            quality: EnumParam::new("Quality", Quality::Standard),
+
+            character: EnumParam::new("Character", Character::Clean),
+
+            drive: FloatParam::new(
+                "Drive",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 18.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1))
+            .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            self_oscillation: BoolParam::new("Self-Oscillation", false),
+
+            phase_mode: EnumParam::new("Phase", PhaseMode::Minimum),
+
+            oversampling: EnumParam::new("Oversampling", Oversampling::Off).non_automatable(),
+
+            exclude_lfe: BoolParam::new("Exclude LFE", true),
+
+            stereo_mode: EnumParam::new("Stereo Mode", StereoMode::Linked),
+
+            side_cutoff: FloatParam::new(
+                "Side/R Cutoff",
+                200.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(2))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz())
+            .with_smoother(SmoothingStyle::Logarithmic(50.0)),
+
+            side_resonance: FloatParam::new(
+                "Side/R Resonance",
+                0.707,
+                FloatRange::Linear {
+                    min: 0.5,
+                    max: 10.0,
+                },
+            )
+            .with_unit(" Q")
+            .with_value_to_string(formatters::v2s_f32_rounded(2))
+            .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            side_slope: EnumParam::new("Side/R Slope", FilterSlope::Slope12dB),
+
+            env_depth: FloatParam::new(
+                "Envelope Depth",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 4.0 },
+            )
+            .with_unit(" oct")
+            .with_value_to_string(formatters::v2s_f32_rounded(2))
+            .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            env_direction: EnumParam::new("Envelope Direction", EnvelopeDirection::Up),
+
+            env_attack: FloatParam::new(
+                "Envelope Attack",
+                10.0,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 200.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            env_release: FloatParam::new(
+                "Envelope Release",
+                150.0,
+                FloatRange::Skewed {
+                    min: 5.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+
+            env_source: EnumParam::new("Envelope Source", EnvelopeSource::Main),
+
+            lfo_depth: FloatParam::new("LFO Depth", 0.0, FloatRange::Linear { min: 0.0, max: 4.0 })
+                .with_unit(" oct")
+                .with_value_to_string(formatters::v2s_f32_rounded(2))
+                .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            lfo_shape: EnumParam::new("LFO Shape", LfoShape::Sine),
+
+            lfo_sync: BoolParam::new("LFO Sync", false),
+
+            lfo_rate: FloatParam::new(
+                "LFO Rate",
+                1.0,
+                FloatRange::Skewed {
+                    min: 0.01,
+                    max: 20.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            lfo_division: EnumParam::new("LFO Division", LfoDivision::Quarter),
+
+            lfo_stereo: FloatParam::new(
+                "LFO Stereo Offset",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 180.0,
+                },
+            )
+            .with_unit("°")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+
+            key_track: FloatParam::new(
+                "Key Track",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 2.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage())
+            .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            key_reference: IntParam::new(
+                "Key Reference",
+                60,
+                IntRange::Linear { min: 0, max: 127 },
+            )
+            .with_value_to_string(formatters::v2s_i32_note_formatter())
+            .with_string_to_value(formatters::s2v_i32_note_formatter()),
+
+            key_glide: FloatParam::new(
+                "Key Glide",
+                0.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+
+            key_velocity: FloatParam::new(
+                "Key Velocity",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 4.0 },
+            )
+            .with_unit(" oct")
+            .with_value_to_string(formatters::v2s_f32_rounded(2))
+            .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            key_mode: EnumParam::new("Key Mode", KeyMode::LastNote),
+
+            dynamic: BoolParam::new("Dynamic", false),
+
+            dyn_threshold: FloatParam::new(
+                "Dynamic Threshold",
+                -24.0,
+                FloatRange::Linear {
+                    min: -60.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            dyn_ratio: FloatParam::new(
+                "Dynamic Ratio",
+                4.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 20.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(":1")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            dyn_attack: FloatParam::new(
+                "Dynamic Attack",
+                5.0,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 100.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            dyn_release: FloatParam::new(
+                "Dynamic Release",
+                150.0,
+                FloatRange::Skewed {
+                    min: 10.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+
+            dyn_range: FloatParam::new(
+                "Dynamic Range",
+                24.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 60.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            bass_mono: BoolParam::new("Bass Mono", false),
+
+            bass_mono_freq: FloatParam::new(
+                "Bass Mono Frequency",
+                120.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 500.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(0))
+            .with_smoother(SmoothingStyle::Logarithmic(50.0)),
+
+            bass_width: FloatParam::new(
+                "Bass Width",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage())
+            .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            dc_mode: BoolParam::new("DC/Subsonic Mode", false),
+
+            dc_cutoff: FloatParam::new(
+                "DC Cutoff",
+                5.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 30.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            mix: FloatParam::new("Mix", 1.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_unit("%")
+                .with_value_to_string(formatters::v2s_f32_percentage(0))
+                .with_string_to_value(formatters::s2v_f32_percentage())
+                .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            phase_matched_dry: BoolParam::new("Phase-Matched Dry", false),
+
+            output_gain: FloatParam::new(
+                "Output",
+                0.0,
+                FloatRange::Linear {
+                    min: -24.0,
+                    max: 24.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1))
+            .with_smoother(SmoothingStyle::Linear(50.0)),
         }
     }
 }
 
+/// Unsmoothed filter settings, used to configure whole chains at once
+#[derive(Clone, Copy, PartialEq)]
+struct FilterSettings {
+    cutoff: f32,
+    resonance: f32,
+    gain: f32,
+    filter_type: FilterType,
+    slope: FilterSlope,
+    alignment: FilterAlignment,
+    ripple: f32,
+}
+
+impl FilterSettings {
+    fn from_params(params: &HighPassParams) -> Self {
+        Self {
+            cutoff: params.cutoff.value(),
+            resonance: params.resonance.value(),
+            gain: params.gain.value(),
+            filter_type: params.filter_type.value(),
+            slope: params.slope.value(),
+            alignment: params.alignment.value(),
+            ripple: params.ripple.value(),
+        }
+    }
+
+    /// Settings of the second filter, which only differ in cutoff,
+    /// resonance and slope
+    fn from_side_params(params: &HighPassParams) -> Self {
+        Self {
+            cutoff: params.side_cutoff.value(),
+            resonance: params.side_resonance.value(),
+            slope: params.side_slope.value(),
+            ..Self::from_params(params)
+        }
+    }
+
+    /// Build a filter chain with these settings
+    fn chain(&self, sample_rate: f32) -> FilterChain {
+        let mut chain = FilterChain::default();
+        chain.set_alignment(to_filter_alignment(self.alignment), self.ripple);
+        chain.update_coefficients(
+            sample_rate,
+            self.cutoff,
+            self.resonance,
+            self.gain,
+            to_filter_type(self.filter_type),
+            to_filter_slope(self.slope),
+        );
+        chain
+    }
+}
+
+/// Time the bypass fades in and out over
+const BYPASS_FADE_MS: f32 = 10.0;
+
+/// Time a slope or filter type change crossfades between chains over
+const SWITCH_FADE_MS: f32 = 5.0;
+
+/// Time bass mono fades in and out over
+const BASS_MONO_FADE_MS: f32 = 10.0;
+
+/// Time dynamic mode fades in and out over
+const DYNAMIC_FADE_MS: f32 = 10.0;
+
+/// Samples between biquad coefficient updates while a parameter is smoothing,
+/// interpolated linearly in between. The SVF engine updates every sample.
+const COEFFICIENT_INTERVAL: u32 = 16;
+
+/// Longest run of frames the filters process as one block
+const MAX_BLOCK: usize = 64;
+
+/// Discrete settings the IIR coefficients were last computed for
+#[derive(Clone, Copy, PartialEq)]
+struct CoefficientKey {
+    filter_type: FilterType,
+    slope: FilterSlope,
+    side_slope: FilterSlope,
+    alignment: FilterAlignment,
+    ripple: f32,
+    engine: FilterEngine,
+    quality: Quality,
+    character: Character,
+    self_oscillation: bool,
+    phase_mode: PhaseMode,
+    oversampling: Oversampling,
+}
+
+/// Smoothed and modulated values of one frame, gathered before its block
+/// is filtered
+#[derive(Clone, Copy, Default)]
+struct FrameControls {
+    cutoff: f32,
+    resonance: f32,
+    gain: f32,
+    drive: f32,
+    side_cutoff: f32,
+    side_resonance: f32,
+    /// Cutoff factors of the main and side filter
+    modulation: [f32; 2],
+    /// Share of the mix the filter is engaged to
+    engagement: f32,
+    mix: f32,
+    output_gain: f32,
+    bypass: f32,
+    bass_mono: f32,
+    bass_mono_freq: f32,
+    bass_width: f32,
+    /// Input averaged over the channels, for the spectrum
+    pre: f32,
+}
+
+/// Channels processed together in one SIMD lane set
+const LANES: usize = 4;
+
+/// Most channels in any supported layout (third-order ambisonics)
+const MAX_CHANNELS: usize = 16;
+
+const LAYOUT_5_1: &str = "5.1";
+const LAYOUT_7_1_4: &str = "7.1.4";
+
+/// Index of the LFE channel for layouts that have one. Both surround
+/// layouts start L R C LFE.
+fn lfe_channel(audio_io_layout: &AudioIOLayout) -> Option<usize> {
+    match audio_io_layout.names.layout {
+        Some(LAYOUT_5_1) | Some(LAYOUT_7_1_4) => Some(3),
+        _ => None,
+    }
+}
+
+/// Oversampling factor of the minimum-phase filter. Character models run
+/// at no less than their own minimum to keep aliasing down.
+fn oversampling_factor(oversampling: Oversampling, character: Option<CharacterModel>) -> usize {
+    match character {
+        Some(_) => oversampling.factor().max(character::MIN_FACTOR),
+        None => oversampling.factor(),
+    }
+}
+
+/// Filter state for up to `LANES` channels sharing one set of coefficients
+#[derive(Clone, Copy, Default)]
+struct ChannelGroup {
+    lanes: FilterChainLanes<LANES>,
+    /// 64-bit chain running in place of `lanes` at high quality
+    precise_lanes: FilterChainLanes<LANES, f64>,
+    use_precise: bool,
+    character: CharacterLanes<LANES>,
+    /// Whether the character model runs in place of the clean chain
+    use_character: bool,
+    oversampler: Oversampler<LANES>,
+    /// All-pass on the delayed dry signal, at the base rate
+    dry: PhaseMatchLanes<LANES>,
+    /// Whether a chain reset itself since the last `sanitize`
+    recovered: bool,
+}
+
+impl ChannelGroup {
+    /// Filter a block of frames, running the chain at the oversampled rate
+    fn process_block(&mut self, frames: &mut [[f32; LANES]]) {
+        let Self {
+            lanes,
+            precise_lanes,
+            use_precise,
+            character,
+            use_character,
+            oversampler,
+            recovered,
+            ..
+        } = self;
+        let recoveries = lanes.recoveries() + precise_lanes.recoveries();
+        if *use_character {
+            oversampler.process_block(frames, |frames| {
+                for frame in frames {
+                    *frame = character.process(*frame);
+                }
+            });
+        } else if *use_precise {
+            oversampler.process_block(frames, |frames| {
+                for run in frames.chunks_mut(MAX_BLOCK) {
+                    let mut precise = [[0.0; LANES]; MAX_BLOCK];
+                    for (precise, frame) in precise.iter_mut().zip(run.iter()) {
+                        *precise = frame.map(f64::from);
+                    }
+                    precise_lanes.process_block(&mut precise[..run.len()]);
+                    for (frame, precise) in run.iter_mut().zip(precise) {
+                        *frame = precise.map(|y| y as f32);
+                    }
+                }
+            });
+        } else {
+            oversampler.process_block(frames, |frames| lanes.process_block(frames));
+        }
+        // The chains recover on their own within a block, but the
+        // oversampler history still holds the bad samples
+        if lanes.recoveries() + precise_lanes.recoveries() != recoveries {
+            oversampler.sanitize();
+            *recovered = true;
+        }
+    }
+
+    /// Filter a block of frames, blending from `previous` while a switch
+    /// fade is at `switch[i]` of the way over on frame `i`
+    fn process_block_switching(
+        &mut self,
+        previous: &mut Self,
+        frames: &mut [[f32; LANES]],
+        switch: &[Option<f32>],
+    ) {
+        let mut faded_out = [[0.0; LANES]; MAX_BLOCK];
+        let faded_out = &mut faded_out[..frames.len()];
+        let fading = switch.iter().any(Option::is_some);
+        if fading {
+            faded_out.copy_from_slice(frames);
+        }
+        self.process_block(frames);
+        if fading {
+            previous.process_block(faded_out);
+            for ((frame, faded_out), t) in frames.iter_mut().zip(&*faded_out).zip(switch) {
+                if let Some(t) = t {
+                    *frame = std::array::from_fn(|lane| {
+                        faded_out[lane] + (frame[lane] - faded_out[lane]) * t
+                    });
+                }
+            }
+        }
+    }
+
+    /// Switch between the 32-bit and 64-bit chains. The chain being
+    /// switched to starts from silence.
+    fn set_precise(&mut self, precise: bool) {
+        if precise != self.use_precise {
+            if precise {
+                self.precise_lanes.reset();
+            } else {
+                self.lanes.reset();
+            }
+            self.use_precise = precise;
+        }
+    }
+
+    /// Flush denormals on the active path and the dry all-pass, and recover
+    /// their lanes from NaN or infinite state. A reset also clears the bad
+    /// samples from the oversampler history.
+    fn sanitize(&mut self) -> bool {
+        let reset = if self.use_character {
+            self.character.sanitize()
+        } else if self.use_precise {
+            self.precise_lanes.sanitize()
+        } else {
+            self.lanes.sanitize()
+        };
+        if reset {
+            self.oversampler.sanitize();
+        }
+        let dry_reset = self.dry.sanitize_state();
+        // Resets the chains made on their own during the buffer count too
+        std::mem::take(&mut self.recovered) || reset || dry_reset
+    }
+
+    /// Configure the character model, or hand back to the clean chain. The
+    /// path being switched to starts from silence.
+    fn set_character(
+        &mut self,
+        model: Option<CharacterModel>,
+        sample_rate: f32,
+        freq: f32,
+        q: f32,
+        drive_db: f32,
+        self_oscillation: bool,
+    ) {
+        match model {
+            Some(model) => {
+                if !self.use_character {
+                    self.character.reset();
+                }
+                self.character
+                    .set(model, sample_rate, freq, q, drive_db, self_oscillation);
+            }
+            None if self.use_character => self.lanes.reset(),
+            None => {}
+        }
+        self.use_character = model.is_some();
+    }
+}
+
+/// Work run off the audio thread
+enum Task {
+    /// Design a linear-phase kernel matching the given chain for one of
+    /// the two filters
+    DesignKernel {
+        chain: FilterChain,
+        sample_rate: f32,
+        slot: usize,
+    },
+    /// Look for a cutoff suggestion in the recording handed over in
+    /// [`LearnExchange`]
+    AnalyzeRecording { sample_rate: f32 },
+}
+
+/// Hand-off of linear-phase kernels between the audio thread and background task
+#[derive(Default)]
+struct KernelExchange {
+    /// Newly designed kernel, picked up by the audio thread
+    designed: Mutex<Option<Kernel>>,
+    /// Kernel replaced on the audio thread, freed by the next background task
+    retired: Mutex<Option<Kernel>>,
+    /// Whether a design task is queued or running
+    busy: AtomicBool,
+}
+
+/// Filters the audio thread is running, with the rate they were designed for
+#[derive(Clone, Copy, Default)]
+struct ActiveResponse {
+    filters: [FilterChain; 2],
+    sample_rate: f32,
+    /// Whether the second filter is in use
+    split: bool,
+    /// Whether the filters are applied as linear-phase kernels
+    linear_phase: bool,
+    /// All-passes on the dry path, without stages when it is only delayed
+    dry: [PhaseMatch; 2],
+    /// Rate of the dry path, which is never oversampled
+    dry_sample_rate: f32,
+    mix: f32,
+    /// Linear output gain
+    output_gain: f32,
+}
+
+impl ActiveResponse {
+    /// Level of the blended output of one filter in dB
+    fn magnitude_db(&self, slot: usize, freq: f32) -> f32 {
+        // Nothing has been published before the first processed buffer
+        if self.sample_rate <= 0.0 {
+            return 0.0;
+        }
+        let wet = self.filters[slot].response(self.sample_rate, freq);
+        // A linear-phase kernel has the chain's magnitude and no phase shift
+        let wet = if self.linear_phase {
+            Complex64::new(wet.norm(), 0.0)
+        } else {
+            wet
+        };
+        let dry = self.dry[slot].response(self.dry_sample_rate, freq);
+        let output = (dry + (wet - dry) * self.mix as f64) * self.output_gain as f64;
+        (20.0 * output.norm().max(1e-12).log10()) as f32
+    }
+}
+
+/// State the audio thread shares with the editor
+#[derive(Default)]
+struct EditorData {
+    response: Mutex<ActiveResponse>,
+    /// Recent audio for the spectrum overlay
+    spectrum: Mutex<SpectrumInput>,
+    /// Times a filter has reset itself after its state went NaN or infinite
+    recoveries: AtomicU32,
+    /// Bits of the output's phase correlation, NaN without a stereo pair
+    correlation: AtomicU32,
+    /// Cutoff learning requested from and reported to the editor
+    learn: LearnExchange,
+    /// Bits of the largest dynamic gain reduction in the last buffer, in dB
+    gain_reduction: AtomicU32,
+    /// Bits of the offset removed from each channel in DC mode, NaN for
+    /// unused or excluded channels, or outside DC mode
+    dc_offsets: [AtomicU32; MAX_CHANNELS],
+}
+
 /// High-pass filter plugin
 struct HighPassFilter {
     params: Arc<HighPassParams>,
     sample_rate: f32,
+    /// Scalar chains the coefficients are designed on: the main filter,
+    /// then the side (or right) filter
     filters: [FilterChain; 2],
+    /// 64-bit counterparts of `filters`, designed only at high quality
+    precise_filters: [FilterChain<f64>; 2],
+    /// Dry path all-passes following the phase of `filters`
+    dry_filters: [PhaseMatch; 2],
+    /// Per-channel filter bank, sized for the layout at initialize
+    channel_groups: Vec<ChannelGroup>,
+    /// Side or right channel state when the stereo pair is not linked
+    side_group: ChannelGroup,
+    /// Filter states from before a slope, type, engine or quality change,
+    /// faded out while the new configuration starts from silence
+    previous_groups: Vec<ChannelGroup>,
+    previous_side_group: ChannelGroup,
+    /// Filter type, slopes, engine and quality the channel groups run with
+    stage_layout: Option<(FilterType, FilterSlope, FilterSlope, FilterEngine, Quality)>,
+    switch_fade: Crossfade,
+    bypass_fade: Crossfade,
+    /// Crossovers splitting off the mid and side lows for bass mono
+    bass_crossovers: [LinkwitzRiley; 2],
+    /// Frequency the crossovers were last set to
+    bass_crossover_freq: f32,
+    bass_mono_fade: Crossfade,
+    correlation: CorrelationMeter,
+    /// Per-channel blockers for DC mode
+    dc_blockers: [DcBlocker; MAX_CHANNELS],
+    /// Whether DC mode ran on the previous buffer
+    was_dc_mode: bool,
+    num_channels: usize,
+    lfe_channel: Option<usize>,
+    /// Keep the dry signal of every channel aligned with the filter latency
+    dry_delays: Vec<DelayLine>,
+    /// Whether the dry all-passes ran on the previous buffer
+    was_phase_matched: bool,
+    /// Level follower modulating the cutoff
+    envelope: EnvelopeFollower,
+    /// Level of the band below the cutoff in dynamic mode
+    detector: DynamicDetector,
+    dynamic_fade: Crossfade,
+    /// Cutoff LFOs for the main and side (or right) filters
+    lfos: [Lfo; 2],
+    /// Note the cutoff follows
+    key_tracker: KeyTracker,
+    /// Input being recorded for cutoff learning
+    recorder: Recorder,
+    was_playing: bool,
+
+    // Coefficient update tracking
+    coefficient_key: Option<CoefficientKey>,
+    coefficient_countdown: u32,
+    was_smoothing: bool,
+
+    // Linear-phase mode, one slot per filter
+    linear_phase: [LinearPhaseFilter; 2],
+    kernel_exchange: Arc<[KernelExchange; 2]>,
+    kernel_settings: [Option<FilterSettings>; 2],
+    latency: u32,
+    /// Chains the editor plots in linear-phase mode, redesigned only when
+    /// their settings change
+    display_settings: [Option<FilterSettings>; 2],
+    display_chains: [FilterChain; 2],
+
+    editor_data: Arc<EditorData>,
 }
 
 impl Default for HighPassFilter {
@@ -82,10 +1340,128 @@ impl Default for HighPassFilter {
             params: Arc::new(HighPassParams::default()),
             sample_rate: 44100.0,
             filters: [FilterChain::default(); 2],
+            precise_filters: [FilterChain::default(); 2],
+            dry_filters: [PhaseMatch::default(); 2],
+            channel_groups: Vec::new(),
+            side_group: ChannelGroup::default(),
+            previous_groups: Vec::new(),
+            previous_side_group: ChannelGroup::default(),
+            stage_layout: None,
+            switch_fade: Crossfade::default(),
+            bypass_fade: Crossfade::default(),
+            bass_crossovers: [LinkwitzRiley::default(); 2],
+            bass_crossover_freq: 0.0,
+            bass_mono_fade: Crossfade::default(),
+            correlation: CorrelationMeter::default(),
+            dc_blockers: [DcBlocker::default(); MAX_CHANNELS],
+            was_dc_mode: false,
+            num_channels: 0,
+            lfe_channel: None,
+            dry_delays: Vec::new(),
+            was_phase_matched: false,
+            envelope: EnvelopeFollower::default(),
+            detector: DynamicDetector::default(),
+            dynamic_fade: Crossfade::default(),
+            lfos: [Lfo::default(); 2],
+            key_tracker: KeyTracker::default(),
+            recorder: Recorder::default(),
+            was_playing: false,
+
+            coefficient_key: None,
+            coefficient_countdown: 0,
+            was_smoothing: false,
+
+            linear_phase: Default::default(),
+            kernel_exchange: Arc::new(Default::default()),
+            kernel_settings: [None; 2],
+            latency: 0,
+            display_settings: [None; 2],
+            display_chains: [FilterChain::default(); 2],
+
+            editor_data: Arc::new(EditorData::default()),
         }
     }
 }
 
+impl HighPassFilter {
+    /// Latency introduced by the given phase mode. Oversampling only
+    /// applies to the minimum-phase filter, and DC mode adds none.
+    fn latency_for(&self, dc_mode: bool, phase_mode: PhaseMode, oversampling_factor: usize) -> u32 {
+        if dc_mode {
+            return 0;
+        }
+        match phase_mode {
+            PhaseMode::Minimum => oversampling::latency_samples(oversampling_factor),
+            PhaseMode::Linear => linear_phase::latency_samples(self.sample_rate),
+        }
+    }
+
+    /// Swap kernels with the background task and request a redesign when
+    /// the settings of a slot have moved since its last one
+    fn update_kernel(
+        &mut self,
+        slot: usize,
+        settings: FilterSettings,
+        context: &mut impl ProcessContext<Self>,
+    ) {
+        let linear_phase = &mut self.linear_phase[slot];
+        let exchange = &self.kernel_exchange[slot];
+
+        if linear_phase.has_retired() {
+            if let Ok(mut retired) = exchange.retired.try_lock() {
+                if retired.is_none() {
+                    *retired = linear_phase.take_retired();
+                }
+            }
+        }
+
+        if linear_phase.can_accept_kernel() {
+            if let Ok(mut designed) = exchange.designed.try_lock() {
+                match designed.take() {
+                    Some(kernel) if kernel.sample_rate() == self.sample_rate => {
+                        linear_phase.set_kernel(kernel);
+                    }
+                    // Stale kernel from before a sample rate change; leave it
+                    // for the next design to replace off the audio thread
+                    stale => *designed = stale,
+                }
+            }
+        }
+
+        if self.kernel_settings[slot] != Some(settings)
+            && !exchange.busy.swap(true, Ordering::AcqRel)
+        {
+            self.kernel_settings[slot] = Some(settings);
+            context.execute_background(Task::DesignKernel {
+                chain: settings.chain(self.sample_rate),
+                sample_rate: self.sample_rate,
+                slot,
+            });
+        }
+    }
+
+    /// Flush denormals in the recursive filters and recover any that went
+    /// NaN or infinite. Returns the number of filters that were reset.
+    fn sanitize_filters(&mut self) -> u32 {
+        let mut recovered = 0;
+        for group in self
+            .channel_groups
+            .iter_mut()
+            .chain(std::iter::once(&mut self.side_group))
+        {
+            recovered += group.sanitize() as u32;
+        }
+        for crossover in &mut self.bass_crossovers {
+            recovered += crossover.sanitize() as u32;
+        }
+        recovered += self.detector.sanitize() as u32;
+        for blocker in &mut self.dc_blockers {
+            recovered += blocker.sanitize() as u32;
+        }
+        recovered
+    }
+}
+
 impl Plugin for HighPassFilter {
     const NAME: &'static str = "High-Pass Filter";
     const VENDOR: &'static str = "trwolf";
@@ -98,85 +1474,1153 @@ impl Plugin for HighPassFilter {
         AudioIOLayout {
             main_input_channels: NonZeroU32::new(2),
             main_output_channels: NonZeroU32::new(2),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            names: PortNames {
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
             ..AudioIOLayout::const_default()
         },
         // Mono
         AudioIOLayout {
             main_input_channels: NonZeroU32::new(1),
             main_output_channels: NonZeroU32::new(1),
+            aux_input_ports: &[new_nonzero_u32(1)],
+            names: PortNames {
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+            ..AudioIOLayout::const_default()
+        },
+        // 5.1 surround
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(6),
+            main_output_channels: NonZeroU32::new(6),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            names: PortNames {
+                layout: Some(LAYOUT_5_1),
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+            ..AudioIOLayout::const_default()
+        },
+        // 7.1.4 immersive
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(12),
+            main_output_channels: NonZeroU32::new(12),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            names: PortNames {
+                layout: Some(LAYOUT_7_1_4),
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+            ..AudioIOLayout::const_default()
+        },
+        // First-order ambisonics
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(4),
+            main_output_channels: NonZeroU32::new(4),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            names: PortNames {
+                layout: Some("Ambisonics (1st order)"),
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+            ..AudioIOLayout::const_default()
+        },
+        // Third-order ambisonics
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(16),
+            main_output_channels: NonZeroU32::new(16),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            names: PortNames {
+                layout: Some("Ambisonics (3rd order)"),
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
             ..AudioIOLayout::const_default()
         },
     ];
 
-    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
 
     type SysExMessage = ();
-    type BackgroundTask = ();
+    type BackgroundTask = Task;
 
     fn params(&self) -> Arc<dyn Params> {
         self.params.clone()
     }
 
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        editor::create(
+            self.params.clone(),
+            self.editor_data.clone(),
+            self.params.editor_state.clone(),
+        )
+    }
+
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let exchanges = self.kernel_exchange.clone();
+        let editor_data = self.editor_data.clone();
+        Box::new(move |task| match task {
+            Task::DesignKernel {
+                chain,
+                sample_rate,
+                slot,
+            } => {
+                let exchange = &exchanges[slot];
+                // Free the kernel the audio thread replaced last time
+                drop(exchange.retired.lock().unwrap().take());
+
+                let kernel = Kernel::design(&chain, sample_rate);
+                *exchange.designed.lock().unwrap() = Some(kernel);
+                exchange.busy.store(false, Ordering::Release);
+            }
+            Task::AnalyzeRecording { sample_rate } => {
+                let exchange = &editor_data.learn;
+                let result = learn::analyze(&exchange.recording.lock().unwrap(), sample_rate);
+                *exchange.result.lock().unwrap() = Some(result);
+            }
+        })
+    }
+
     fn initialize(
         &mut self,
-        _audio_io_layout: &AudioIOLayout,
+        audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
         self.sample_rate = buffer_config.sample_rate;
 
-        let cutoff = self.params.cutoff.value();
-        let resonance = self.params.resonance.value();
-        let slope = self.params.slope.value();
+        self.num_channels = audio_io_layout
+            .main_output_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+        if self.num_channels > MAX_CHANNELS {
+            return false;
+        }
+        self.lfe_channel = lfe_channel(audio_io_layout);
+
+        let settings = [
+            FilterSettings::from_params(&self.params),
+            FilterSettings::from_side_params(&self.params),
+        ];
+        let character = to_character_model(self.params.character.value());
+        let factor = oversampling_factor(self.params.oversampling.value(), character);
+        let oversampled_rate = self.sample_rate * factor as f32;
+        self.filters = settings.map(|settings| settings.chain(oversampled_rate));
+        self.coefficient_key = None;
 
-        for filter in &mut self.filters {
-            filter.update_coefficients(self.sample_rate, cutoff, resonance, to_filter_slope(slope));
+        let mut group = ChannelGroup::default();
+        group.oversampler.set_factor(factor);
+        group.lanes.copy_coefficients(&self.filters[0]);
+        self.channel_groups = vec![group; self.num_channels.div_ceil(LANES)];
+        self.side_group = group;
+        self.side_group.lanes.copy_coefficients(&self.filters[1]);
+        self.previous_groups = self.channel_groups.clone();
+        self.stage_layout = None;
+
+        self.switch_fade.set_time(self.sample_rate, SWITCH_FADE_MS);
+        self.bypass_fade.set_time(self.sample_rate, BYPASS_FADE_MS);
+        self.bypass_fade
+            .fade_to(if self.params.bypass.value() { 1.0 } else { 0.0 });
+        self.bypass_fade.finish();
+        self.bass_mono_fade
+            .set_time(self.sample_rate, BASS_MONO_FADE_MS);
+        self.dynamic_fade
+            .set_time(self.sample_rate, DYNAMIC_FADE_MS);
+        self.dynamic_fade.fade_to(if self.params.dynamic.value() {
+            1.0
+        } else {
+            0.0
+        });
+        self.dynamic_fade.finish();
+        // Forces the crossovers to be set on the next sample
+        self.bass_crossover_freq = 0.0;
+        self.correlation.set_sample_rate(self.sample_rate);
+
+        // The side slot only ever filters one channel of a stereo pair
+        let slot_channels = [self.num_channels, usize::from(self.num_channels == 2)];
+        for ((linear_phase, settings), num_channels) in self
+            .linear_phase
+            .iter_mut()
+            .zip(&settings)
+            .zip(slot_channels)
+        {
+            let kernel = Kernel::design(&settings.chain(self.sample_rate), self.sample_rate);
+            *linear_phase = LinearPhaseFilter::new(num_channels, kernel);
         }
+        self.kernel_settings = settings.map(Some);
+        self.display_settings = [None; 2];
+
+        let max_latency = linear_phase::latency_samples(self.sample_rate)
+            .max(oversampling::latency_samples(oversampling::MAX_FACTOR));
+        self.dry_delays = (0..self.num_channels)
+            .map(|_| DelayLine::new(max_latency as usize))
+            .collect();
+
+        self.editor_data
+            .spectrum
+            .lock()
+            .unwrap()
+            .set_sample_rate(self.sample_rate);
+
+        // The detector band is redesigned for the new rate on the next
+        // coefficient refresh
+        self.detector = DynamicDetector::default();
+
+        // A recording in progress is dropped along with its sample rate
+        self.recorder = Recorder::new(self.sample_rate);
+        *self.editor_data.learn.recording.lock().unwrap() =
+            vec![0.0; learn::recording_len(self.sample_rate)];
+        self.editor_data
+            .learn
+            .listening
+            .store(false, Ordering::Relaxed);
+
+        self.latency = self.latency_for(
+            self.params.dc_mode.value(),
+            self.params.phase_mode.value(),
+            factor,
+        );
+        context.set_latency_samples(self.latency);
 
         true
     }
 
     fn reset(&mut self) {
-        for filter in &mut self.filters {
-            filter.reset();
+        for group in self
+            .channel_groups
+            .iter_mut()
+            .chain(std::iter::once(&mut self.side_group))
+        {
+            group.lanes.reset();
+            group.precise_lanes.reset();
+            group.character.reset();
+            group.oversampler.reset();
+            group.dry.reset();
+        }
+        for dry_delay in &mut self.dry_delays {
+            dry_delay.reset();
+        }
+        self.switch_fade.finish();
+        self.bypass_fade.finish();
+        self.bass_mono_fade.finish();
+        self.dynamic_fade.finish();
+        for crossover in &mut self.bass_crossovers {
+            crossover.reset();
+        }
+        self.correlation.reset();
+        for blocker in &mut self.dc_blockers {
+            blocker.reset();
+        }
+        self.envelope.reset();
+        self.detector.reset();
+        self.key_tracker.reset();
+        for linear_phase in &mut self.linear_phase {
+            linear_phase.reset();
         }
+
+        // Input from before the reset would run into input from after it
+        self.recorder.stop();
+        self.editor_data
+            .learn
+            .listening
+            .store(false, Ordering::Relaxed);
     }
 
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        let num_channels = buffer.channels();
+        let phase_mode = self.params.phase_mode.value();
+        let oversampling = self.params.oversampling.value();
+        let exclude_lfe = self.params.exclude_lfe.value();
+        let character = to_character_model(self.params.character.value())
+            .filter(|_| phase_mode == PhaseMode::Minimum);
+        let self_oscillation = self.params.self_oscillation.value();
+        let factor = oversampling_factor(oversampling, character);
+
+        self.envelope.set_times(
+            self.sample_rate,
+            self.params.env_attack.value(),
+            self.params.env_release.value(),
+        );
+        let dynamic = self.params.dynamic.value();
+        self.detector.set_times(
+            self.sample_rate,
+            self.params.dyn_attack.value(),
+            self.params.dyn_release.value(),
+        );
+        let dyn_threshold = self.params.dyn_threshold.value();
+        let dyn_ratio = self.params.dyn_ratio.value();
+        let dyn_range = self.params.dyn_range.value();
+        // The detector starts from silence whenever it fades in from off
+        if dynamic && self.dynamic_fade.position() == 0.0 {
+            self.detector.reset();
+        }
+        self.dynamic_fade.fade_to(if dynamic { 1.0 } else { 0.0 });
+        let mut max_gain_reduction = 0.0f32;
+        // Last sample's share of the mix the filter was engaged to
+        let mut engagement = 1.0;
+        let env_direction = match self.params.env_direction.value() {
+            EnvelopeDirection::Up => 1.0,
+            EnvelopeDirection::Down => -1.0,
+        };
+
+        let transport = context.transport();
+        if transport.playing && !self.was_playing {
+            self.lfos[0].set_phase(0.0);
+        }
+        self.was_playing = transport.playing;
+        let lfo_shape = to_lfo_shape(self.params.lfo_shape.value());
+        let lfo_offset = self.params.lfo_stereo.value() / 360.0;
+        let lfo_rate = if self.params.lfo_sync.value() {
+            let beats = self.params.lfo_division.value().beats();
+            // Synced cycles follow the song position while playing
+            if let (true, Some(pos_beats)) = (transport.playing, transport.pos_beats()) {
+                self.lfos[0].set_phase((pos_beats / beats).rem_euclid(1.0) as f32);
+            }
+            (transport.tempo.unwrap_or(120.0) / 60.0 / beats) as f32
+        } else {
+            self.params.lfo_rate.value()
+        };
+        self.lfos[1].set_phase(self.lfos[0].phase() + lfo_offset);
+        let lfo_increment = lfo_rate / self.sample_rate;
+
+        self.key_tracker
+            .set_glide(self.sample_rate, self.params.key_glide.value());
+        let key_mode = to_key_mode(self.params.key_mode.value());
+        let key_reference = self.params.key_reference.value() as f32;
+        let key_tracking =
+            self.params.key_track.value() != 0.0 || self.params.key_velocity.value() > 0.0;
+        let mut next_event = context.next_event();
+
+        let sidechain = match self.params.env_source.value() {
+            EnvelopeSource::Main => None,
+            EnvelopeSource::Sidechain => aux.inputs.first().map(|input| input.as_slice_immutable()),
+        };
+        for group in self
+            .channel_groups
+            .iter_mut()
+            .chain(std::iter::once(&mut self.side_group))
+        {
+            if group.oversampler.factor() != factor {
+                group.oversampler.set_factor(factor);
+            }
+        }
+        let oversampled_rate = self.sample_rate * factor as f32;
+
+        // Stereo modes only apply to a plain stereo pair
+        let stereo = self.num_channels == 2;
+        let stereo_mode = if stereo {
+            self.params.stereo_mode.value()
+        } else {
+            StereoMode::Linked
+        };
+        // A stereo LFO offset needs separate coefficients for L and R, so a
+        // linked pair then runs through both filters with the main settings
+        let split_lfo = stereo
+            && phase_mode == PhaseMode::Minimum
+            && stereo_mode == StereoMode::Linked
+            && lfo_offset > 0.0
+            && self.params.lfo_depth.value() > 0.0;
+        let linked = stereo_mode == StereoMode::Linked && !split_lfo;
+        // Channel that skips the filters but keeps their latency: the
+        // unfiltered half of a mid/side pair, or the LFE
+        let dry_channel = stereo_mode
+            .dry_channel()
+            .map(|channel| (channel, true))
+            .or(self.lfe_channel.map(|channel| (channel, exclude_lfe)));
+
+        let dc_mode = self.params.dc_mode.value();
+        if dc_mode {
+            // Starting from a settled estimate would need the offset known
+            // in advance, so every engagement starts from zero
+            if !self.was_dc_mode {
+                for blocker in &mut self.dc_blockers {
+                    blocker.reset();
+                }
+            }
+            let cutoff = self.params.dc_cutoff.value();
+            for blocker in &mut self.dc_blockers {
+                blocker.set_cutoff(self.sample_rate, cutoff);
+            }
+        }
+        self.was_dc_mode = dc_mode;
 
-        for mut channel_samples in buffer.iter_samples() {
-            let cutoff = self.params.cutoff.smoothed.next();
-            let resonance = self.params.resonance.smoothed.next();
-            let slope = self.params.slope.value();
+        // Linear-phase kernels add no phase shift of their own to match.
+        // Dynamic mode blends like a partial mix, and would notch at the
+        // cutoff against an unmatched dry signal.
+        let phase_matched = !dc_mode
+            && phase_mode == PhaseMode::Minimum
+            && (self.params.phase_matched_dry.value()
+                || dynamic
+                || self.dynamic_fade.position() > 0.0);
+        if phase_matched && !self.was_phase_matched {
+            for group in self
+                .channel_groups
+                .iter_mut()
+                .chain(std::iter::once(&mut self.side_group))
+            {
+                group.dry.reset();
+            }
+        }
+        self.was_phase_matched = phase_matched;
+
+        let latency = self.latency_for(dc_mode, phase_mode, factor);
+        if latency != self.latency {
+            self.latency = latency;
+            context.set_latency_samples(latency);
+        }
+        if !dc_mode && phase_mode == PhaseMode::Linear {
+            self.update_kernel(0, FilterSettings::from_params(&self.params), context);
+            if stereo {
+                self.update_kernel(1, FilterSettings::from_side_params(&self.params), context);
+            }
+        }
 
-            for i in 0..num_channels.min(2) {
-                self.filters[i].update_coefficients(
-                    self.sample_rate,
+        let key = CoefficientKey {
+            filter_type: self.params.filter_type.value(),
+            slope: self.params.slope.value(),
+            side_slope: self.params.side_slope.value(),
+            alignment: self.params.alignment.value(),
+            ripple: self.params.ripple.value(),
+            engine: self.params.engine.value(),
+            quality: self.params.quality.value(),
+            character: self.params.character.value(),
+            self_oscillation,
+            phase_mode,
+            oversampling,
+        };
+        // New stages would start from whatever state the old ones left, and
+        // a new engine or precision starts from silence anyway, so a new
+        // layout starts from silence and fades in over the old one
+        let stage_layout = (
+            key.filter_type,
+            key.slope,
+            key.side_slope,
+            key.engine,
+            key.quality,
+        );
+        if phase_mode == PhaseMode::Minimum
+            && character.is_none()
+            && self
+                .stage_layout
+                .is_some_and(|layout| layout != stage_layout)
+        {
+            self.previous_groups.copy_from_slice(&self.channel_groups);
+            self.previous_side_group = self.side_group;
+            for group in self
+                .channel_groups
+                .iter_mut()
+                .chain(std::iter::once(&mut self.side_group))
+            {
+                group.lanes.reset();
+                group.precise_lanes.reset();
+            }
+            self.switch_fade.restart();
+        }
+        self.stage_layout = Some(stage_layout);
+        self.bypass_fade
+            .fade_to(if self.params.bypass.value() { 1.0 } else { 0.0 });
+        // The crossovers start from silence whenever they fade in from off
+        let bass_mono = stereo && self.params.bass_mono.value();
+        if bass_mono && self.bass_mono_fade.position() == 0.0 {
+            for crossover in &mut self.bass_crossovers {
+                crossover.reset();
+            }
+        }
+        self.bass_mono_fade
+            .fade_to(if bass_mono { 1.0 } else { 0.0 });
+        let mut refresh = self.coefficient_key != Some(key);
+        // Changed settings take their coefficients at once rather than
+        // ramping from the old ones
+        let mut jump = refresh;
+        self.coefficient_key = Some(key);
+        if refresh {
+            for filter in &mut self.filters {
+                filter.set_engine(to_filter_engine(key.engine));
+                filter.set_alignment(to_filter_alignment(key.alignment), key.ripple);
+            }
+            for filter in &mut self.precise_filters {
+                filter.set_engine(to_filter_engine(key.engine));
+                filter.set_alignment(to_filter_alignment(key.alignment), key.ripple);
+            }
+        }
+        // At high quality the 32-bit chains only feed the response curve
+        let precise = key.quality == Quality::High;
+        // Zero-delay-feedback structures take new coefficients every sample
+        let coefficient_interval = match (key.engine, character) {
+            (FilterEngine::Biquad, None) => COEFFICIENT_INTERVAL,
+            _ => 1,
+        };
+
+        let editor_open = self.params.editor_state.is_open();
+        let editor_data = self.editor_data.clone();
+        let mut spectrum = editor_open
+            .then(|| editor_data.spectrum.try_lock().ok())
+            .flatten();
+        let learn = &editor_data.learn;
+        if !self.recorder.is_active() && learn.requested.swap(false, Ordering::AcqRel) {
+            self.recorder.start();
+            learn.listening.store(true, Ordering::Relaxed);
+        }
+
+        // Minimum-phase filtering runs in blocks: the smoothers and the
+        // modulation advance per sample, then the chain runs over the whole
+        // block
+        let filtering = !dc_mode && phase_mode == PhaseMode::Minimum;
+        // Linear-phase kernels and the DC blockers take no coefficient
+        // refreshes, so there the detector band follows the cutoff per buffer
+        if !filtering {
+            self.detector
+                .set_cutoff(self.sample_rate, self.params.cutoff.value());
+        }
+        let num_samples = buffer.samples();
+        let channels = buffer.as_slice();
+        let mut block_start = 0;
+        while block_start < num_samples {
+            // Notes move the cutoff from the sample they arrive on
+            while let Some(event) = next_event {
+                if event.timing() > block_start as u32 {
+                    break;
+                }
+                match event {
+                    NoteEvent::NoteOn { note, velocity, .. } => {
+                        self.key_tracker.note_on(note, velocity, key_mode);
+                        refresh |= key_tracking;
+                    }
+                    NoteEvent::NoteOff { note, .. } => {
+                        self.key_tracker.note_off(note);
+                        refresh |= key_tracking;
+                    }
+                    _ => {}
+                }
+                next_event = context.next_event();
+            }
+
+            // Active modulation moves the cutoff like a smoother does
+            let smoothing = self.params.cutoff.smoothed.is_smoothing()
+                || self.params.resonance.smoothed.is_smoothing()
+                || self.params.gain.smoothed.is_smoothing()
+                || self.params.drive.smoothed.is_smoothing()
+                || self.params.side_cutoff.smoothed.is_smoothing()
+                || self.params.side_resonance.smoothed.is_smoothing()
+                || self.params.env_depth.smoothed.is_smoothing()
+                || self.params.env_depth.value() > 0.0
+                || self.params.lfo_depth.smoothed.is_smoothing()
+                || self.params.lfo_depth.value() > 0.0
+                || self.params.key_track.smoothed.is_smoothing()
+                || self.params.key_velocity.smoothed.is_smoothing()
+                || self.key_tracker.is_gliding();
+
+            // A block ends at the next note and, while smoothing, at the next
+            // coefficient update. Smoothing itself only starts on a buffer
+            // boundary, as automation splits the buffer.
+            let mut block_len = (num_samples - block_start).min(MAX_BLOCK);
+            if let Some(event) = next_event {
+                block_len = block_len.min(event.timing() as usize - block_start);
+            }
+            // Coefficients only move while a smoother is active: recompute
+            // as soon as smoothing starts, then every few samples, and once
+            // more on the settled value, ramping to each over an interval
+            let mut ramp = false;
+            if filtering {
+                if smoothing {
+                    if !self.was_smoothing || self.coefficient_countdown == 0 {
+                        refresh = true;
+                        ramp = true;
+                        self.coefficient_countdown = coefficient_interval;
+                    }
+                    block_len = block_len.min(self.coefficient_countdown as usize);
+                    self.coefficient_countdown -= block_len as u32;
+                } else if self.was_smoothing {
+                    refresh = true;
+                    ramp = true;
+                }
+                self.was_smoothing = smoothing;
+            }
+
+            let mut controls = [FrameControls::default(); MAX_BLOCK];
+            let mut inputs = [[0.0; MAX_CHANNELS]; MAX_BLOCK];
+            for (i, (controls, input)) in controls[..block_len]
+                .iter_mut()
+                .zip(&mut inputs)
+                .enumerate()
+            {
+                let sample_idx = block_start + i;
+                let cutoff = self.params.cutoff.smoothed.next();
+                let resonance = self.params.resonance.smoothed.next();
+                let gain = self.params.gain.smoothed.next();
+                let drive = self.params.drive.smoothed.next();
+                let side_cutoff = self.params.side_cutoff.smoothed.next();
+                let side_resonance = self.params.side_resonance.smoothed.next();
+                let env_depth = self.params.env_depth.smoothed.next();
+                let lfo_depth = self.params.lfo_depth.smoothed.next();
+                let key_track = self.params.key_track.smoothed.next();
+                let key_velocity = self.params.key_velocity.smoothed.next();
+                let (side_cutoff, side_resonance) = if stereo_mode == StereoMode::Linked {
+                    (cutoff, resonance)
+                } else {
+                    (side_cutoff, side_resonance)
+                };
+
+                let mut frame = [0.0; MAX_CHANNELS];
+                for (value, channel) in frame.iter_mut().zip(channels.iter()) {
+                    *value = channel[sample_idx];
+                }
+
+                let key_level = match sidechain {
+                    Some(channels) => channels
+                        .iter()
+                        .fold(0.0f32, |peak, channel| peak.max(channel[sample_idx].abs())),
+                    None => frame.iter().fold(0.0f32, |peak, x| peak.max(x.abs())),
+                };
+                self.envelope.process(key_level);
+                let pre = frame.iter().sum::<f32>() / self.num_channels.max(1) as f32;
+                // Switching dynamic mode fades between the detected
+                // engagement and full engagement
+                let dynamic_fade = self.dynamic_fade.next();
+                engagement = 1.0;
+                if dynamic_fade > 0.0 {
+                    let key = match sidechain {
+                        Some(channels) => {
+                            channels
+                                .iter()
+                                .map(|channel| channel[sample_idx])
+                                .sum::<f32>()
+                                / channels.len().max(1) as f32
+                        }
+                        None => pre,
+                    };
+                    let gain_reduction = dynamics::gain_reduction_db(
+                        self.detector.process(key),
+                        dyn_threshold,
+                        dyn_ratio,
+                        dyn_range,
+                    );
+                    max_gain_reduction = max_gain_reduction.max(gain_reduction);
+                    // The filtered signal replaces the dry one as far as the
+                    // low band is to be reduced
+                    let detected = 1.0 - util::db_to_gain(-gain_reduction);
+                    engagement += dynamic_fade * (detected - 1.0);
+                }
+                // A full recording waits here while the last analysis still
+                // holds the exchange
+                if self.recorder.is_active() && self.recorder.push(pre) {
+                    if let Ok(mut recording) = learn.recording.try_lock() {
+                        self.recorder.swap_into(&mut recording);
+                        learn.listening.store(false, Ordering::Relaxed);
+                        context.execute_background(Task::AnalyzeRecording {
+                            sample_rate: self.sample_rate,
+                        });
+                    }
+                }
+                let env_octaves = env_direction * env_depth * self.envelope.normalized();
+                let key_octaves = match self.key_tracker.next() {
+                    Some(note) => {
+                        key_track * (note - key_reference) / 12.0
+                            + key_velocity * self.key_tracker.velocity()
+                    }
+                    None => 0.0,
+                };
+                let modulation = self.lfos.map(|lfo| {
+                    (env_octaves + key_octaves + lfo_depth * lfo.value(lfo_shape)).exp2()
+                });
+                for lfo in &mut self.lfos {
+                    lfo.advance(lfo_increment);
+                }
+                if stereo_mode.is_mid_side() {
+                    let [left, right, ..] = frame;
+                    frame[0] = 0.5 * (left + right);
+                    frame[1] = 0.5 * (left - right);
+                }
+
+                *input = frame;
+                *controls = FrameControls {
                     cutoff,
                     resonance,
-                    to_filter_slope(slope),
-                );
+                    gain,
+                    drive,
+                    side_cutoff,
+                    side_resonance,
+                    modulation,
+                    engagement,
+                    mix: self.params.mix.smoothed.next(),
+                    output_gain: util::db_to_gain(self.params.output_gain.smoothed.next()),
+                    bypass: self.bypass_fade.next(),
+                    bass_mono: self.bass_mono_fade.next(),
+                    bass_mono_freq: self.params.bass_mono_freq.smoothed.next(),
+                    bass_width: self.params.bass_width.smoothed.next(),
+                    pre,
+                };
+            }
+
+            let mut frames = inputs;
+            if dc_mode {
+                for frame in &mut frames[..block_len] {
+                    for (sample, blocker) in frame[..self.num_channels]
+                        .iter_mut()
+                        .zip(&mut self.dc_blockers)
+                    {
+                        *sample = blocker.process(*sample);
+                    }
+                }
+            } else if phase_mode == PhaseMode::Linear {
+                for frame in &mut frames[..block_len] {
+                    if linked {
+                        self.linear_phase[0].process_frame(frame[..self.num_channels].iter_mut());
+                    } else {
+                        let [first, second, ..] = frame;
+                        self.linear_phase[0].process_frame(std::iter::once(first));
+                        self.linear_phase[1].process_frame(std::iter::once(second));
+                    }
+                }
+            } else {
+                if refresh {
+                    refresh = false;
+                    // Ramps run at the oversampled rate
+                    let ramp_samples = if ramp && !jump {
+                        coefficient_interval * factor as u32
+                    } else {
+                        0
+                    };
+                    jump = false;
+                    let FrameControls {
+                        cutoff,
+                        resonance,
+                        gain,
+                        drive,
+                        side_cutoff,
+                        side_resonance,
+                        modulation,
+                        ..
+                    } = controls[0];
+                    let side_slope = if stereo_mode == StereoMode::Linked {
+                        key.slope
+                    } else {
+                        key.side_slope
+                    };
+                    // Character models are high-passes with a slope of their
+                    // own; the clean chains then only feed the response curve
+                    let (filter_type, slope, side_slope) = match character {
+                        Some(model) => (filter::FilterType::HighPass, model.slope(), model.slope()),
+                        None => (
+                            to_filter_type(key.filter_type),
+                            to_filter_slope(key.slope),
+                            to_filter_slope(side_slope),
+                        ),
+                    };
+                    // The detector band ends at the unmodulated cutoff
+                    self.detector.set_cutoff(self.sample_rate, cutoff);
+                    let main_cutoff = (cutoff * modulation[0]).clamp(20.0, 20_000.0);
+                    let side_cutoff = (side_cutoff * modulation[1]).clamp(20.0, 20_000.0);
+
+                    let [main, side] = &mut self.filters;
+                    let [precise_main, precise_side] = &mut self.precise_filters;
+                    let [main_dry, side_dry] = &mut self.dry_filters;
+                    main.update_coefficients(
+                        oversampled_rate,
+                        main_cutoff,
+                        resonance,
+                        gain,
+                        filter_type,
+                        slope,
+                    );
+                    main_dry.follow(main, oversampled_rate, self.sample_rate, filter_type);
+                    if precise {
+                        precise_main.update_coefficients(
+                            oversampled_rate,
+                            main_cutoff,
+                            resonance,
+                            gain,
+                            filter_type,
+                            slope,
+                        );
+                    }
+                    for group in &mut self.channel_groups {
+                        group.lanes.ramp_coefficients(main, ramp_samples);
+                        group
+                            .dry
+                            .ramp_coefficients(main_dry, ramp_samples / factor as u32);
+                        if precise {
+                            group
+                                .precise_lanes
+                                .ramp_coefficients(precise_main, ramp_samples);
+                        }
+                        group.set_precise(precise);
+                        group.set_character(
+                            character,
+                            oversampled_rate,
+                            main_cutoff,
+                            resonance,
+                            drive,
+                            self_oscillation,
+                        );
+                    }
+                    if stereo {
+                        side.update_coefficients(
+                            oversampled_rate,
+                            side_cutoff,
+                            side_resonance,
+                            gain,
+                            filter_type,
+                            side_slope,
+                        );
+                        self.side_group.lanes.ramp_coefficients(side, ramp_samples);
+                        side_dry.follow(side, oversampled_rate, self.sample_rate, filter_type);
+                        self.side_group
+                            .dry
+                            .ramp_coefficients(side_dry, ramp_samples / factor as u32);
+                        if precise {
+                            precise_side.update_coefficients(
+                                oversampled_rate,
+                                side_cutoff,
+                                side_resonance,
+                                gain,
+                                filter_type,
+                                side_slope,
+                            );
+                            self.side_group
+                                .precise_lanes
+                                .ramp_coefficients(precise_side, ramp_samples);
+                        }
+                        self.side_group.set_precise(precise);
+                        self.side_group.set_character(
+                            character,
+                            oversampled_rate,
+                            side_cutoff,
+                            side_resonance,
+                            drive,
+                            self_oscillation,
+                        );
+                    }
+                }
+
+                let mut switch = [None; MAX_BLOCK];
+                for switch in &mut switch[..block_len] {
+                    *switch = self
+                        .switch_fade
+                        .is_fading()
+                        .then(|| self.switch_fade.next());
+                }
+                let switch = &switch[..block_len];
+                if linked {
+                    for (index, (group, previous)) in self
+                        .channel_groups
+                        .iter_mut()
+                        .zip(&mut self.previous_groups)
+                        .enumerate()
+                    {
+                        let range = index * LANES..(index + 1) * LANES;
+                        let mut block = [[0.0; LANES]; MAX_BLOCK];
+                        for (values, frame) in block.iter_mut().zip(&frames[..block_len]) {
+                            values.copy_from_slice(&frame[range.clone()]);
+                        }
+                        group.process_block_switching(previous, &mut block[..block_len], switch);
+                        for (frame, values) in frames.iter_mut().zip(&block[..block_len]) {
+                            frame[range.clone()].copy_from_slice(values);
+                        }
+                    }
+                } else {
+                    let mut first = [[0.0; LANES]; MAX_BLOCK];
+                    let mut second = [[0.0; LANES]; MAX_BLOCK];
+                    for ((first, second), frame) in
+                        first.iter_mut().zip(&mut second).zip(&frames[..block_len])
+                    {
+                        first[0] = frame[0];
+                        second[0] = frame[1];
+                    }
+                    self.channel_groups[0].process_block_switching(
+                        &mut self.previous_groups[0],
+                        &mut first[..block_len],
+                        switch,
+                    );
+                    self.side_group.process_block_switching(
+                        &mut self.previous_side_group,
+                        &mut second[..block_len],
+                        switch,
+                    );
+                    for ((frame, first), second) in
+                        frames[..block_len].iter_mut().zip(first).zip(second)
+                    {
+                        frame[0] = first[0];
+                        frame[1] = second[0];
+                    }
+                }
             }
 
-            for (channel_idx, sample) in channel_samples.iter_mut().enumerate() {
-                if channel_idx < 2 {
-                    *sample = self.filters[channel_idx].process(*sample);
+            for (i, ((mut frame, input), controls)) in frames
+                .into_iter()
+                .zip(inputs)
+                .zip(controls)
+                .take(block_len)
+                .enumerate()
+            {
+                let FrameControls {
+                    engagement,
+                    mix,
+                    output_gain,
+                    bypass,
+                    bass_mono,
+                    bass_mono_freq,
+                    bass_width,
+                    pre,
+                    ..
+                } = controls;
+
+                // Every channel always feeds its delay so changing the mix or
+                // the LFE option never plays stale samples
+                let mut delayed = [0.0; MAX_CHANNELS];
+                for ((delayed, input), dry_delay) in
+                    delayed.iter_mut().zip(input).zip(&mut self.dry_delays)
+                {
+                    *delayed = dry_delay.process(input, self.latency as usize);
+                }
+                let mut dry = delayed;
+                let bypassed = dry_channel
+                    .filter(|&(_, bypass)| bypass)
+                    .map(|(channel, _)| (channel, dry[channel]));
+                if phase_matched {
+                    if linked {
+                        for (group, values) in self
+                            .channel_groups
+                            .iter_mut()
+                            .zip(dry.chunks_exact_mut(LANES))
+                        {
+                            let input = std::array::from_fn(|lane| values[lane]);
+                            values.copy_from_slice(&group.dry.process(input));
+                        }
+                    } else {
+                        dry[0] = self.channel_groups[0].dry.process([dry[0], 0.0, 0.0, 0.0])[0];
+                        dry[1] = self.side_group.dry.process([dry[1], 0.0, 0.0, 0.0])[0];
+                    }
+                }
+                // A bypassed channel is wet and dry at once
+                if let Some((channel, sample)) = bypassed {
+                    frame[channel] = sample;
+                    dry[channel] = sample;
+                }
+                for (wet, dry) in frame.iter_mut().zip(dry) {
+                    *wet = output_gain * (dry + (*wet - dry) * mix * engagement);
+                }
+
+                if bass_mono > 0.0 {
+                    if bass_mono_freq != self.bass_crossover_freq {
+                        for crossover in &mut self.bass_crossovers {
+                            crossover.set_frequency(self.sample_rate, bass_mono_freq);
+                        }
+                        self.bass_crossover_freq = bass_mono_freq;
+                    }
+                    let [first, second, ..] = frame;
+                    let (mid, side) = if stereo_mode.is_mid_side() {
+                        (first, second)
+                    } else {
+                        (0.5 * (first + second), 0.5 * (first - second))
+                    };
+                    // Both signals go through the crossover so the mid keeps
+                    // the same phase as the side
+                    let (mid_low, mid_high) = self.bass_crossovers[0].split(mid);
+                    let (side_low, side_high) = self.bass_crossovers[1].split(side);
+                    let (mid, side) = (mid_low + mid_high, bass_width * side_low + side_high);
+                    let (first_out, second_out) = if stereo_mode.is_mid_side() {
+                        (mid, side)
+                    } else {
+                        (mid + side, mid - side)
+                    };
+                    frame[0] = first + (first_out - first) * bass_mono;
+                    frame[1] = second + (second_out - second) * bass_mono;
+                }
+
+                // Bypassing lands on the delayed input, so the latency the
+                // host compensates for holds either way
+                for (output, delayed) in frame.iter_mut().zip(delayed) {
+                    *output += (delayed - *output) * bypass;
+                }
+
+                if stereo_mode.is_mid_side() {
+                    let [mid, side, ..] = frame;
+                    frame[0] = mid + side;
+                    frame[1] = mid - side;
+                }
+
+                if stereo {
+                    self.correlation.process(frame[0], frame[1]);
                 }
+
+                if let Some(spectrum) = spectrum.as_mut() {
+                    let post = frame.iter().sum::<f32>() / self.num_channels.max(1) as f32;
+                    spectrum.push(pre, post);
+                }
+
+                for (channel, value) in channels.iter_mut().zip(frame) {
+                    channel[block_start + i] = value;
+                }
+            }
+            block_start += block_len;
+        }
+
+        self.editor_data
+            .gain_reduction
+            .store(max_gain_reduction.to_bits(), Ordering::Relaxed);
+
+        let correlation = if stereo {
+            self.correlation.value()
+        } else {
+            f32::NAN
+        };
+        self.editor_data
+            .correlation
+            .store(correlation.to_bits(), Ordering::Relaxed);
+
+        let mut dc_offsets = [f32::NAN; MAX_CHANNELS];
+        if dc_mode {
+            for (offset, blocker) in dc_offsets
+                .iter_mut()
+                .zip(&self.dc_blockers)
+                .take(self.num_channels)
+            {
+                *offset = blocker.dc();
+            }
+            // A channel passing unfiltered has nothing removed: the dry half
+            // of a mid/side pair adds no offset to left and right, and an
+            // excluded LFE shows no meter
+            if let Some((channel, true)) = dry_channel {
+                dc_offsets[channel] = if stereo_mode.dry_channel().is_some() {
+                    0.0
+                } else {
+                    f32::NAN
+                };
+            }
+            // The blockers run on mid and side, the meter shows left and right
+            if stereo_mode.is_mid_side() {
+                let [mid, side, ..] = dc_offsets;
+                dc_offsets[0] = mid + side;
+                dc_offsets[1] = mid - side;
+            }
+        }
+        for (stored, offset) in self.editor_data.dc_offsets.iter().zip(dc_offsets) {
+            stored.store(offset.to_bits(), Ordering::Relaxed);
+        }
+
+        // Once per buffer is enough to keep denormals from building up and
+        // to catch a blown-up filter before the next buffer
+        let recovered = self.sanitize_filters();
+        if recovered > 0 {
+            self.editor_data
+                .recoveries
+                .fetch_add(recovered, Ordering::Relaxed);
+        }
+        // A bad sample can come from any stage, or from the input itself, so
+        // the output is checked whether or not a filter had to recover
+        for channel in buffer.as_slice() {
+            for sample in channel.iter_mut().filter(|sample| !sample.is_finite()) {
+                *sample = 0.0;
             }
         }
 
+        if editor_open {
+            let filters = if phase_mode == PhaseMode::Linear {
+                let settings = [
+                    FilterSettings::from_params(&self.params),
+                    FilterSettings::from_side_params(&self.params),
+                ];
+                for ((cached, chain), settings) in self
+                    .display_settings
+                    .iter_mut()
+                    .zip(&mut self.display_chains)
+                    .zip(settings)
+                {
+                    if *cached != Some(settings) {
+                        *chain = settings.chain(self.sample_rate);
+                        *cached = Some(settings);
+                    }
+                }
+                self.display_chains
+            } else {
+                self.filters
+            };
+            let sample_rate = if phase_mode == PhaseMode::Linear {
+                self.sample_rate
+            } else {
+                oversampled_rate
+            };
+            let dry = if phase_matched {
+                self.dry_filters
+            } else {
+                Default::default()
+            };
+            if let Ok(mut response) = self.editor_data.response.try_lock() {
+                *response = ActiveResponse {
+                    filters,
+                    sample_rate,
+                    split: !linked,
+                    linear_phase: phase_mode == PhaseMode::Linear,
+                    dry,
+                    dry_sample_rate: self.sample_rate,
+                    mix: self.params.mix.value() * engagement,
+                    output_gain: util::db_to_gain(self.params.output_gain.value()),
+                };
+            }
+        }
+
+        // Nothing consumed the refresh (empty buffer, DC mode or linear
+        // phase), so force one on the next buffer
+        if refresh {
+            self.coefficient_key = None;
+        }
+
         ProcessStatus::Normal
     }
 }
 
+/// Convert plugin enum to filter module enum
+fn to_filter_type(filter_type: FilterType) -> filter::FilterType {
+    match filter_type {
+        FilterType::HighPass => filter::FilterType::HighPass,
+        FilterType::LowPass => filter::FilterType::LowPass,
+        FilterType::BandPass => filter::FilterType::BandPass,
+        FilterType::Notch => filter::FilterType::Notch,
+        FilterType::Peak => filter::FilterType::Peak,
+        FilterType::LowShelf => filter::FilterType::LowShelf,
+        FilterType::HighShelf => filter::FilterType::HighShelf,
+        FilterType::AllPass => filter::FilterType::AllPass,
+    }
+}
+
+/// Convert plugin enum to filter module enum
+fn to_filter_alignment(alignment: FilterAlignment) -> filter::FilterAlignment {
+    match alignment {
+        FilterAlignment::Butterworth => filter::FilterAlignment::Butterworth,
+        FilterAlignment::LinkwitzRiley => filter::FilterAlignment::LinkwitzRiley,
+        FilterAlignment::Bessel => filter::FilterAlignment::Bessel,
+        FilterAlignment::Chebyshev => filter::FilterAlignment::Chebyshev,
+    }
+}
+
+/// Convert plugin enum to filter module enum
+fn to_filter_engine(engine: FilterEngine) -> filter::FilterEngine {
+    match engine {
+        FilterEngine::Biquad => filter::FilterEngine::Biquad,
+        FilterEngine::Svf => filter::FilterEngine::Svf,
+    }
+}
+
+/// Convert plugin enum to character model, `None` for the clean filter
+fn to_character_model(character: Character) -> Option<CharacterModel> {
+    match character {
+        Character::Clean => None,
+        Character::SallenKey => Some(CharacterModel::SallenKey),
+        Character::Ladder => Some(CharacterModel::Ladder),
+        Character::Diode => Some(CharacterModel::Diode),
+    }
+}
+
 /// Convert plugin enum to filter module enum
 fn to_filter_slope(slope: FilterSlope) -> filter::FilterSlope {
     match slope {
@@ -184,6 +2628,43 @@ fn to_filter_slope(slope: FilterSlope) -> filter::FilterSlope {
         FilterSlope::Slope12dB => filter::FilterSlope::Slope12dB,
         FilterSlope::Slope18dB => filter::FilterSlope::Slope18dB,
         FilterSlope::Slope24dB => filter::FilterSlope::Slope24dB,
+        FilterSlope::Slope36dB => filter::FilterSlope::Slope36dB,
+        FilterSlope::Slope48dB => filter::FilterSlope::Slope48dB,
+        FilterSlope::Slope72dB => filter::FilterSlope::Slope72dB,
+        FilterSlope::Slope96dB => filter::FilterSlope::Slope96dB,
+    }
+}
+
+/// Convert filter module enum to plugin enum
+fn from_filter_slope(slope: filter::FilterSlope) -> FilterSlope {
+    match slope {
+        filter::FilterSlope::Slope6dB => FilterSlope::Slope6dB,
+        filter::FilterSlope::Slope12dB => FilterSlope::Slope12dB,
+        filter::FilterSlope::Slope18dB => FilterSlope::Slope18dB,
+        filter::FilterSlope::Slope24dB => FilterSlope::Slope24dB,
+        filter::FilterSlope::Slope36dB => FilterSlope::Slope36dB,
+        filter::FilterSlope::Slope48dB => FilterSlope::Slope48dB,
+        filter::FilterSlope::Slope72dB => FilterSlope::Slope72dB,
+        filter::FilterSlope::Slope96dB => FilterSlope::Slope96dB,
+    }
+}
+
+/// Convert plugin enum to key tracking module enum
+fn to_key_mode(mode: KeyMode) -> keytrack::KeyMode {
+    match mode {
+        KeyMode::LastNote => keytrack::KeyMode::LastNote,
+        KeyMode::Legato => keytrack::KeyMode::Legato,
+    }
+}
+
+/// Convert plugin enum to LFO module enum
+fn to_lfo_shape(shape: LfoShape) -> lfo::LfoShape {
+    match shape {
+        LfoShape::Sine => lfo::LfoShape::Sine,
+        LfoShape::Triangle => lfo::LfoShape::Triangle,
+        LfoShape::Saw => lfo::LfoShape::Saw,
+        LfoShape::Square => lfo::LfoShape::Square,
+        LfoShape::SampleAndHold => lfo::LfoShape::SampleAndHold,
     }
 }
 
@@ -197,6 +2678,8 @@ impl ClapPlugin for HighPassFilter {
         ClapFeature::Filter,
         ClapFeature::Stereo,
         ClapFeature::Mono,
+        ClapFeature::Surround,
+        ClapFeature::Ambisonic,
     ];
 }
 
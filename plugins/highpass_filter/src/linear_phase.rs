@@ -0,0 +1,356 @@
+use num_complex::Complex32;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::filter::{FilterChain, FrequencyResponse};
+
+/// Partition size of the uniformly partitioned convolution
+pub const BLOCK_SIZE: usize = 256;
+
+/// FIR length for a sample rate, roughly a quarter second so the
+/// lowest cutoffs still resolve
+pub fn kernel_length(sample_rate: f32) -> usize {
+    ((sample_rate * 0.25) as usize)
+        .next_power_of_two()
+        .max(2 * BLOCK_SIZE)
+}
+
+/// Latency in samples: half the kernel plus one partition of buffering
+pub fn latency_samples(sample_rate: f32) -> u32 {
+    (kernel_length(sample_rate) / 2 + BLOCK_SIZE) as u32
+}
+
+/// Linear-phase FIR kernel, stored as the spectra of its partitions
+#[derive(Default)]
+pub struct Kernel {
+    partitions: Vec<Vec<Complex32>>,
+    sample_rate: f32,
+}
+
+impl Kernel {
+    /// Design a kernel with the magnitude response of `chain` and a constant
+    /// delay of half the kernel length, by frequency sampling and windowing
+    pub fn design(chain: &FilterChain, sample_rate: f32) -> Self {
+        let length = kernel_length(sample_rate);
+        let mut planner = RealFftPlanner::<f32>::new();
+
+        // Zero-phase spectrum sampled at the FFT bins
+        let inverse = planner.plan_fft_inverse(length);
+        let mut spectrum = inverse.make_input_vec();
+        for (bin, value) in spectrum.iter_mut().enumerate() {
+            let freq = bin as f32 * sample_rate / length as f32;
+            *value = Complex32::new(chain.magnitude(sample_rate, freq), 0.0);
+        }
+        let mut impulse = inverse.make_output_vec();
+        inverse
+            .process(&mut spectrum, &mut impulse)
+            .expect("IFFT processing failed");
+
+        // Centre the impulse and apply a Blackman window around the centre
+        let half = length / 2;
+        let taps: Vec<f32> = (0..length)
+            .map(|n| {
+                let phase = 2.0 * PI * n as f32 / length as f32;
+                let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+                impulse[(n + half) % length] / length as f32 * window
+            })
+            .collect();
+
+        // Zero-padded partition spectra for overlap-save
+        let forward = planner.plan_fft_forward(2 * BLOCK_SIZE);
+        let partitions = taps
+            .chunks(BLOCK_SIZE)
+            .map(|chunk| {
+                let mut padded = forward.make_input_vec();
+                padded[..chunk.len()].copy_from_slice(chunk);
+                let mut partition = forward.make_output_vec();
+                forward
+                    .process(&mut padded, &mut partition)
+                    .expect("FFT processing failed");
+                partition
+            })
+            .collect();
+
+        Self {
+            partitions,
+            sample_rate,
+        }
+    }
+
+    /// Sample rate the kernel was designed for
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn num_partitions(&self) -> usize {
+        self.partitions.len()
+    }
+}
+
+/// Uniformly partitioned overlap-save convolver for one channel
+pub struct Convolver {
+    fft: Arc<dyn RealToComplex<f32>>,
+    ifft: Arc<dyn ComplexToReal<f32>>,
+    /// Previous and current input block
+    input_frame: Vec<f32>,
+    input_pos: usize,
+    output_block: Vec<f32>,
+    /// Frequency-domain delay line of past input blocks
+    delay_line: Vec<Vec<Complex32>>,
+    delay_pos: usize,
+    fft_input: Vec<f32>,
+    accumulator: Vec<Complex32>,
+    time: Vec<f32>,
+    scratch: Vec<Complex32>,
+}
+
+impl Convolver {
+    /// Create a convolver for kernels with the given number of partitions
+    pub fn new(num_partitions: usize) -> Self {
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(2 * BLOCK_SIZE);
+        let ifft = planner.plan_fft_inverse(2 * BLOCK_SIZE);
+
+        let scratch_len = fft.get_scratch_len().max(ifft.get_scratch_len());
+
+        Self {
+            input_frame: vec![0.0; 2 * BLOCK_SIZE],
+            input_pos: 0,
+            output_block: vec![0.0; BLOCK_SIZE],
+            delay_line: vec![fft.make_output_vec(); num_partitions.max(1)],
+            delay_pos: 0,
+            fft_input: fft.make_input_vec(),
+            accumulator: fft.make_output_vec(),
+            time: ifft.make_output_vec(),
+            scratch: vec![Complex32::default(); scratch_len],
+            fft,
+            ifft,
+        }
+    }
+
+    /// Whether the next sample completes an input block
+    pub fn at_block_end(&self) -> bool {
+        self.input_pos == BLOCK_SIZE - 1
+    }
+
+    /// Process a single sample. When `next` is given and this sample completes
+    /// a block, the following block crossfades from `kernel` to `next`.
+    pub fn process(&mut self, input: f32, kernel: &Kernel, next: Option<&Kernel>) -> f32 {
+        let output = self.output_block[self.input_pos];
+        self.input_frame[BLOCK_SIZE + self.input_pos] = input;
+        self.input_pos += 1;
+
+        if self.input_pos == BLOCK_SIZE {
+            self.input_pos = 0;
+            self.process_block(kernel, next);
+        }
+
+        output
+    }
+
+    fn process_block(&mut self, kernel: &Kernel, next: Option<&Kernel>) {
+        let num_partitions = self.delay_line.len();
+        self.delay_pos = (self.delay_pos + num_partitions - 1) % num_partitions;
+
+        self.fft_input.copy_from_slice(&self.input_frame);
+        self.fft
+            .process_with_scratch(
+                &mut self.fft_input,
+                &mut self.delay_line[self.delay_pos],
+                &mut self.scratch,
+            )
+            .expect("FFT processing failed");
+        self.input_frame.copy_within(BLOCK_SIZE.., 0);
+
+        self.convolve(kernel);
+        self.output_block.copy_from_slice(&self.time[BLOCK_SIZE..]);
+
+        if let Some(next) = next {
+            self.convolve(next);
+            for (i, (output, &faded_in)) in self
+                .output_block
+                .iter_mut()
+                .zip(&self.time[BLOCK_SIZE..])
+                .enumerate()
+            {
+                let t = (i + 1) as f32 / BLOCK_SIZE as f32;
+                *output += (faded_in - *output) * t;
+            }
+        }
+    }
+
+    /// Multiply-accumulate the delay line with the kernel partitions into `time`
+    fn convolve(&mut self, kernel: &Kernel) {
+        let num_partitions = self.delay_line.len().min(kernel.num_partitions());
+
+        self.accumulator.fill(Complex32::default());
+        for p in 0..num_partitions {
+            let input = &self.delay_line[(self.delay_pos + p) % self.delay_line.len()];
+            for ((acc, x), h) in self
+                .accumulator
+                .iter_mut()
+                .zip(input)
+                .zip(&kernel.partitions[p])
+            {
+                *acc += x * h;
+            }
+        }
+
+        // DC and Nyquist are real for real signals; clear rounding residue
+        self.accumulator[0].im = 0.0;
+        self.accumulator[BLOCK_SIZE].im = 0.0;
+
+        self.ifft
+            .process_with_scratch(&mut self.accumulator, &mut self.time, &mut self.scratch)
+            .expect("IFFT processing failed");
+
+        let scale = 1.0 / (2 * BLOCK_SIZE) as f32;
+        for sample in &mut self.time {
+            *sample *= scale;
+        }
+    }
+
+    /// Clear all buffered input and output
+    pub fn reset(&mut self) {
+        self.input_frame.fill(0.0);
+        self.output_block.fill(0.0);
+        for spectrum in &mut self.delay_line {
+            spectrum.fill(Complex32::default());
+        }
+        self.input_pos = 0;
+    }
+}
+
+/// Linear-phase filter for all channels, sharing one kernel
+#[derive(Default)]
+pub struct LinearPhaseFilter {
+    kernel: Kernel,
+    /// Newly designed kernel, swapped in at the next block boundary
+    pending: Option<Kernel>,
+    /// Replaced kernel waiting to be freed off the audio thread
+    retired: Option<Kernel>,
+    convolvers: Vec<Convolver>,
+}
+
+impl LinearPhaseFilter {
+    /// Create a filter for `num_channels` channels
+    pub fn new(num_channels: usize, kernel: Kernel) -> Self {
+        Self {
+            convolvers: (0..num_channels)
+                .map(|_| Convolver::new(kernel.num_partitions()))
+                .collect(),
+            kernel,
+            pending: None,
+            retired: None,
+        }
+    }
+
+    /// Whether a new kernel can be queued
+    pub fn can_accept_kernel(&self) -> bool {
+        self.pending.is_none()
+    }
+
+    /// Queue a kernel; it fades in over the block after the next boundary
+    pub fn set_kernel(&mut self, kernel: Kernel) {
+        self.pending = Some(kernel);
+    }
+
+    /// Whether a replaced kernel is waiting to be freed
+    pub fn has_retired(&self) -> bool {
+        self.retired.is_some()
+    }
+
+    /// Take the last replaced kernel so it can be freed elsewhere
+    pub fn take_retired(&mut self) -> Option<Kernel> {
+        self.retired.take()
+    }
+
+    /// Process one sample frame in place, one sample per channel
+    pub fn process_frame<'a>(&mut self, samples: impl Iterator<Item = &'a mut f32>) {
+        let swapping = self.pending.is_some()
+            && self.retired.is_none()
+            && self.convolvers.first().is_some_and(Convolver::at_block_end);
+
+        let next = if swapping {
+            self.pending.as_ref()
+        } else {
+            None
+        };
+        for (convolver, sample) in self.convolvers.iter_mut().zip(samples) {
+            *sample = convolver.process(*sample, &self.kernel, next);
+        }
+
+        if let (true, Some(kernel)) = (swapping, self.pending.take()) {
+            self.retired = Some(std::mem::replace(&mut self.kernel, kernel));
+        }
+    }
+
+    /// Clear the convolution state of every channel
+    pub fn reset(&mut self) {
+        for convolver in &mut self.convolvers {
+            convolver.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{FilterSlope, FilterType};
+
+    fn impulse_response(chain: &FilterChain, sample_rate: f32) -> Vec<f32> {
+        let mut filter = LinearPhaseFilter::new(1, Kernel::design(chain, sample_rate));
+        let length = latency_samples(sample_rate) as usize * 2;
+
+        (0..length)
+            .map(|n| {
+                let mut sample = if n == 0 { 1.0 } else { 0.0 };
+                filter.process_frame(std::iter::once(&mut sample));
+                sample
+            })
+            .collect()
+    }
+
+    fn highpass(slope: FilterSlope) -> FilterChain {
+        let mut chain = FilterChain::default();
+        chain.update_coefficients(
+            44100.0,
+            100.0,
+            std::f32::consts::FRAC_1_SQRT_2,
+            0.0,
+            FilterType::HighPass,
+            slope,
+        );
+        chain
+    }
+
+    #[test]
+    fn test_impulse_response_is_symmetric() {
+        let response = impulse_response(&highpass(FilterSlope::Slope24dB), 44100.0);
+        let latency = latency_samples(44100.0) as usize;
+
+        let peak = response
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap())
+            .map(|(i, _)| i)
+            .unwrap();
+        assert_eq!(
+            peak, latency,
+            "Kernel should be centred on the reported latency"
+        );
+
+        for k in 1..kernel_length(44100.0) / 2 {
+            assert!((response[latency + k] - response[latency - k]).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_dc_rejection() {
+        let response = impulse_response(&highpass(FilterSlope::Slope12dB), 44100.0);
+        let dc_gain: f32 = response.iter().sum();
+
+        assert!(dc_gain.abs() < 1e-3, "Linear-phase HPF should reject DC");
+    }
+}
@@ -0,0 +1,358 @@
+use std::f64::consts::PI;
+
+/// Highest supported oversampling factor
+pub const MAX_FACTOR: usize = 8;
+
+const MAX_STAGES: usize = 3;
+
+/// Base-rate frames upsampled at once
+const BLOCK: usize = 64;
+
+/// Half-band filter length is 4 * K + 3 taps
+const K: usize = 19;
+const CENTER: usize = 2 * K + 1;
+/// Non-zero taps on the even polyphase branch
+const PHASE_TAPS: usize = 2 * K + 2;
+/// Most input samples a stage is delayed by to make its latency whole
+const MAX_PADDING: usize = (1 << (MAX_STAGES - 1)) - 1;
+
+/// Kaiser window shape, roughly -100 dB stopband so nonlinear stages can
+/// keep their aliasing below -90 dB
+const KAISER_BETA: f64 = 10.0;
+
+/// Even-indexed taps of a Kaiser-windowed half-band low-pass. The odd
+/// taps are all zero apart from the 0.5 centre tap.
+fn half_band_taps() -> [f32; PHASE_TAPS] {
+    let length = 4 * K + 3;
+    let mut taps = [0.0; PHASE_TAPS];
+
+    for (j, tap) in taps.iter_mut().enumerate() {
+        let n = 2 * j;
+        let x = (n as f64 - CENTER as f64) / 2.0;
+        let sinc = (PI * x).sin() / (PI * x);
+        let ratio = 2.0 * n as f64 / (length - 1) as f64 - 1.0;
+        let window = bessel_i0(KAISER_BETA * (1.0 - ratio * ratio).sqrt()) / bessel_i0(KAISER_BETA);
+        *tap = 0.5 * sinc * window;
+    }
+
+    // Both polyphase branches must have exactly half the DC gain
+    let sum: f64 = taps.iter().sum();
+    taps.map(|tap| (tap * 0.5 / sum) as f32)
+}
+
+/// Zeroth-order modified Bessel function of the first kind
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for k in 1..32 {
+        term *= (x / (2.0 * k as f64)).powi(2);
+        sum += term;
+    }
+    sum
+}
+
+/// Input samples stage `index` is delayed by. The half-band centre is odd,
+/// so the deeper stages alone would add a fraction of a base-rate sample.
+fn stage_padding(index: usize) -> usize {
+    let step = 1 << index;
+    (step - CENTER % step) % step
+}
+
+/// One 2x stage: polyphase half-band interpolator and decimator, running
+/// `N` channel lanes side by side
+#[derive(Clone, Copy)]
+struct HalfBandStage<const N: usize> {
+    taps: [f32; PHASE_TAPS],
+    /// Input samples the upsampler lags behind by
+    padding: usize,
+    /// Upsampler input history, newest first
+    up_history: [[f32; N]; PHASE_TAPS + MAX_PADDING],
+    /// Decimator even-sample history, newest first
+    down_even: [[f32; N]; PHASE_TAPS],
+    /// Decimator odd-sample history, newest first
+    down_odd: [[f32; N]; K + 2],
+}
+
+impl<const N: usize> HalfBandStage<N> {
+    fn new(padding: usize) -> Self {
+        Self {
+            taps: half_band_taps(),
+            padding,
+            up_history: [[0.0; N]; PHASE_TAPS + MAX_PADDING],
+            down_even: [[0.0; N]; PHASE_TAPS],
+            down_odd: [[0.0; N]; K + 2],
+        }
+    }
+}
+
+/// Per-lane dot product of the half-band taps with a history
+#[inline]
+fn convolve_lanes<const N: usize>(taps: &[f32; PHASE_TAPS], history: &[[f32; N]]) -> [f32; N] {
+    let mut sum = [0.0; N];
+    for (h, x) in taps.iter().zip(history) {
+        for (acc, x) in sum.iter_mut().zip(x) {
+            *acc += h * x;
+        }
+    }
+    sum
+}
+
+impl<const N: usize> HalfBandStage<N> {
+    /// Produce two output frames for one input frame
+    #[inline]
+    fn upsample(&mut self, input: [f32; N]) -> ([f32; N], [f32; N]) {
+        self.up_history
+            .copy_within(..PHASE_TAPS + MAX_PADDING - 1, 1);
+        self.up_history[0] = input;
+
+        let history = &self.up_history[self.padding..];
+        let even = convolve_lanes(&self.taps, history).map(|x| 2.0 * x);
+        // The odd branch is the centre tap alone: a pure delay
+        let odd = history[K];
+
+        (even, odd)
+    }
+
+    /// Produce one output frame for two input frames
+    #[inline]
+    fn downsample(&mut self, even: [f32; N], odd: [f32; N]) -> [f32; N] {
+        self.down_even.copy_within(..PHASE_TAPS - 1, 1);
+        self.down_even[0] = even;
+
+        let mut output = convolve_lanes(&self.taps, &self.down_even);
+        for (y, x) in output.iter_mut().zip(&self.down_odd[K]) {
+            *y += 0.5 * x;
+        }
+
+        self.down_odd.copy_within(..K + 1, 1);
+        self.down_odd[0] = odd;
+
+        output
+    }
+
+    /// Zero any NaN or infinite samples in the histories
+    fn sanitize(&mut self) {
+        for x in self
+            .up_history
+            .iter_mut()
+            .chain(&mut self.down_even)
+            .chain(&mut self.down_odd)
+            .flatten()
+        {
+            if !x.is_finite() {
+                *x = 0.0;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.up_history = [[0.0; N]; PHASE_TAPS + MAX_PADDING];
+        self.down_even = [[0.0; N]; PHASE_TAPS];
+        self.down_odd = [[0.0; N]; K + 2];
+    }
+}
+
+/// Latency in samples at the base rate. Every stage delays by its padding
+/// at its input rate, and by the half-band centre on the way up and again
+/// on the way down at its own rate, which adds up to whole samples.
+pub fn latency_samples(factor: usize) -> u32 {
+    let num_stages = factor.max(1).trailing_zeros() as usize;
+    (0..num_stages)
+        .map(|index| ((CENTER + stage_padding(index)) >> index) as u32)
+        .sum()
+}
+
+/// Cascaded 2x/4x/8x oversampler for `N` channels processed as lanes
+#[derive(Clone, Copy)]
+pub struct Oversampler<const N: usize> {
+    stages: [HalfBandStage<N>; MAX_STAGES],
+    num_stages: usize,
+}
+
+impl<const N: usize> Default for Oversampler<N> {
+    fn default() -> Self {
+        Self {
+            stages: std::array::from_fn(|index| HalfBandStage::new(stage_padding(index))),
+            num_stages: 0,
+        }
+    }
+}
+
+impl<const N: usize> Oversampler<N> {
+    /// Current oversampling factor
+    pub fn factor(&self) -> usize {
+        1 << self.num_stages
+    }
+
+    /// Change the oversampling factor (1, 2, 4 or 8), clearing all state
+    pub fn set_factor(&mut self, factor: usize) {
+        self.num_stages = (factor.clamp(1, MAX_FACTOR).trailing_zeros() as usize).min(MAX_STAGES);
+        self.reset();
+    }
+
+    /// Run a block of base-rate frames through `f` at the oversampled rate,
+    /// upsampling up to `BLOCK` frames at a time
+    pub fn process_block(&mut self, frames: &mut [[f32; N]], mut f: impl FnMut(&mut [[f32; N]])) {
+        if self.num_stages == 0 {
+            f(frames);
+            return;
+        }
+
+        let mut first = [[0.0; N]; BLOCK * MAX_FACTOR];
+        let mut second = [[0.0; N]; BLOCK * MAX_FACTOR];
+        for run in frames.chunks_mut(BLOCK) {
+            let (mut work, mut next) = (&mut first, &mut second);
+            let mut len = run.len();
+            work[..len].copy_from_slice(run);
+
+            for stage in &mut self.stages[..self.num_stages] {
+                for i in 0..len {
+                    (next[2 * i], next[2 * i + 1]) = stage.upsample(work[i]);
+                }
+                std::mem::swap(&mut work, &mut next);
+                len *= 2;
+            }
+
+            f(&mut work[..len]);
+
+            for stage in self.stages[..self.num_stages].iter_mut().rev() {
+                len /= 2;
+                for i in 0..len {
+                    next[i] = stage.downsample(work[2 * i], work[2 * i + 1]);
+                }
+                std::mem::swap(&mut work, &mut next);
+            }
+
+            run.copy_from_slice(&work[..len]);
+        }
+    }
+
+    /// Zero any NaN or infinite samples in the interpolator and decimator
+    /// state, leaving the finite ones, so a lane that went bad can recover
+    /// without disturbing the others
+    pub fn sanitize(&mut self) {
+        for stage in &mut self.stages {
+            stage.sanitize();
+        }
+    }
+
+    /// Clear all interpolator and decimator state
+    pub fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_matches_impulse() {
+        for factor in [2, 4, 8] {
+            let mut oversampler = Oversampler::<1>::default();
+            oversampler.set_factor(factor);
+
+            let mut frames = vec![[0.0]; 128];
+            frames[0] = [1.0];
+            oversampler.process_block(&mut frames, |_| {});
+            let response: Vec<f32> = frames.iter().map(|[x]| *x).collect();
+            let peak = response
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap())
+                .map(|(i, _)| i)
+                .unwrap();
+
+            assert_eq!(peak as u32, latency_samples(factor));
+        }
+    }
+
+    #[test]
+    fn test_latency_is_whole_samples() {
+        // A tone well inside the passband must line up with the input
+        // delayed by the reported latency, not just peak near it
+        let sample_rate = 44100.0;
+        for factor in [2, 4, 8] {
+            let mut oversampler = Oversampler::<1>::default();
+            oversampler.set_factor(factor);
+
+            let input: Vec<f32> = (0..4096)
+                .map(|n| {
+                    let phase = std::f64::consts::TAU * 1000.0 * n as f64 / sample_rate;
+                    phase.sin() as f32
+                })
+                .collect();
+            let mut frames: Vec<[f32; 1]> = input.iter().map(|&x| [x]).collect();
+            oversampler.process_block(&mut frames, |_| {});
+
+            let latency = latency_samples(factor) as usize;
+            let error = frames[latency + 512..]
+                .iter()
+                .zip(&input[512..])
+                .fold(0.0f32, |error, ([y], x)| error.max((y - x).abs()));
+            assert!(
+                error < 1e-3,
+                "{}x is off by {} against {} samples",
+                factor,
+                error,
+                latency
+            );
+        }
+    }
+
+    #[test]
+    fn test_passband_is_transparent() {
+        let sample_rate = 44100.0;
+        for factor in [2, 4, 8] {
+            let mut oversampler = Oversampler::<1>::default();
+            oversampler.set_factor(factor);
+
+            let mut frames: Vec<[f32; 1]> = (0..8192)
+                .map(|n| {
+                    let phase = std::f64::consts::TAU * 1000.0 * n as f64 / sample_rate;
+                    [phase.sin() as f32]
+                })
+                .collect();
+            oversampler.process_block(&mut frames, |_| {});
+            let peak = frames[4096..]
+                .iter()
+                .fold(0.0f32, |peak, [x]| peak.max(x.abs()));
+
+            assert!(
+                (peak - 1.0).abs() < 0.01,
+                "{}x passband gain {}",
+                factor,
+                peak
+            );
+        }
+    }
+
+    #[test]
+    fn test_block_length_does_not_change_output() {
+        let input: Vec<[f32; 2]> = (0..300)
+            .map(|n| [(n as f32 * 0.37).sin(), (n as f32 * 0.11).cos()])
+            .collect();
+        // A nonlinearity, so each oversampled frame must reach it in order
+        let shape = |frames: &mut [[f32; 2]]| {
+            for x in frames.iter_mut().flatten() {
+                *x = x.tanh();
+            }
+        };
+
+        let mut whole = Oversampler::<2>::default();
+        whole.set_factor(4);
+        let mut expected = input.clone();
+        whole.process_block(&mut expected, shape);
+
+        let mut split = Oversampler::<2>::default();
+        split.set_factor(4);
+        let mut output = input;
+        for run in output.chunks_mut(7) {
+            split.process_block(run, shape);
+        }
+
+        assert_eq!(output, expected);
+    }
+}
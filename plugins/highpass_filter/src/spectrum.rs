@@ -0,0 +1,162 @@
+use num_complex::Complex32;
+use realfft::{RealFftPlanner, RealToComplex};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// Analysis window length
+pub const FFT_SIZE: usize = 2048;
+
+/// Floor of the displayed spectrum
+pub const MIN_DB: f32 = -96.0;
+
+/// How far each new analysis pulls the displayed spectrum
+const SMOOTHING: f32 = 0.3;
+
+/// Most recent pre- and post-filter samples, written by the audio thread
+pub struct SpectrumInput {
+    pre: Vec<f32>,
+    post: Vec<f32>,
+    write_pos: usize,
+    sample_rate: f32,
+}
+
+impl Default for SpectrumInput {
+    fn default() -> Self {
+        Self {
+            pre: vec![0.0; FFT_SIZE],
+            post: vec![0.0; FFT_SIZE],
+            write_pos: 0,
+            sample_rate: 44100.0,
+        }
+    }
+}
+
+impl SpectrumInput {
+    /// Set the rate the pushed samples run at
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Push one pre-filter and one post-filter sample
+    #[inline]
+    pub fn push(&mut self, pre: f32, post: f32) {
+        self.pre[self.write_pos] = pre;
+        self.post[self.write_pos] = post;
+        self.write_pos = (self.write_pos + 1) % FFT_SIZE;
+    }
+}
+
+/// Smoothed pre- and post-filter magnitude spectra, analyzed on the editor thread
+pub struct Spectrum {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    /// Scale that reads a full-scale sine as 0 dBFS
+    window_gain: f32,
+    fft_input: Vec<f32>,
+    fft_output: Vec<Complex32>,
+    pre_db: Vec<f32>,
+    post_db: Vec<f32>,
+    sample_rate: f32,
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(FFT_SIZE);
+        let window: Vec<f32> = (0..FFT_SIZE)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / FFT_SIZE as f32).cos())
+            .collect();
+        let window_gain = 2.0 / window.iter().sum::<f32>();
+        let num_bins = FFT_SIZE / 2 + 1;
+
+        Self {
+            fft_input: fft.make_input_vec(),
+            fft_output: fft.make_output_vec(),
+            fft,
+            window,
+            window_gain,
+            pre_db: vec![MIN_DB; num_bins],
+            post_db: vec![MIN_DB; num_bins],
+            sample_rate: 44100.0,
+        }
+    }
+}
+
+impl Spectrum {
+    /// Analyze the latest input and fold it into the displayed spectra
+    pub fn update(&mut self, input: &SpectrumInput) {
+        self.sample_rate = input.sample_rate;
+
+        self.transform(&input.pre, input.write_pos);
+        accumulate(&mut self.pre_db, &self.fft_output, self.window_gain);
+        self.transform(&input.post, input.write_pos);
+        accumulate(&mut self.post_db, &self.fft_output, self.window_gain);
+    }
+
+    /// Window a ring buffer, oldest sample first, and transform it
+    fn transform(&mut self, samples: &[f32], write_pos: usize) {
+        let (newest, oldest) = samples.split_at(write_pos);
+        for ((x, sample), w) in self
+            .fft_input
+            .iter_mut()
+            .zip(oldest.iter().chain(newest))
+            .zip(&self.window)
+        {
+            *x = sample * w;
+        }
+        self.fft
+            .process(&mut self.fft_input, &mut self.fft_output)
+            .expect("FFT processing failed");
+    }
+
+    /// Pre-filter level at `freq` in dBFS
+    pub fn pre_db(&self, freq: f32) -> f32 {
+        self.interpolate(&self.pre_db, freq)
+    }
+
+    /// Post-filter level at `freq` in dBFS
+    pub fn post_db(&self, freq: f32) -> f32 {
+        self.interpolate(&self.post_db, freq)
+    }
+
+    fn interpolate(&self, spectrum: &[f32], freq: f32) -> f32 {
+        let position = (freq / self.sample_rate * FFT_SIZE as f32).max(0.0);
+        let index = (position as usize).min(spectrum.len() - 2);
+        let t = (position - index as f32).min(1.0);
+        spectrum[index] + (spectrum[index + 1] - spectrum[index]) * t
+    }
+}
+
+/// Smooth the magnitudes of `bins` into a dB spectrum
+fn accumulate(spectrum: &mut [f32], bins: &[Complex32], gain: f32) {
+    for (db, bin) in spectrum.iter_mut().zip(bins) {
+        let magnitude = bin.norm() * gain;
+        let new_db = (20.0 * magnitude.max(1e-9).log10()).max(MIN_DB);
+        *db += (new_db - *db) * SMOOTHING;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sine_peaks_at_its_frequency() {
+        let sample_rate = 48000.0;
+        let mut input = SpectrumInput::default();
+        input.set_sample_rate(sample_rate);
+        for n in 0..FFT_SIZE + 100 {
+            let phase = 2.0 * std::f64::consts::PI * 1000.0 * n as f64 / sample_rate as f64;
+            let sample = phase.sin() as f32;
+            input.push(sample, 0.1 * sample);
+        }
+
+        let mut spectrum = Spectrum::default();
+        for _ in 0..32 {
+            spectrum.update(&input);
+        }
+
+        assert!(spectrum.pre_db(1000.0).abs() < 2.0);
+        assert!((spectrum.post_db(1000.0) + 20.0).abs() < 2.0);
+        assert!(spectrum.pre_db(5000.0) < -60.0);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::filter::BiquadState;
+use crate::lanes::BiquadLanes;
+use crate::{BandType, MAX_CHANNELS};
+
+/// Highest design frequency relative to the sample rate, keeping the
+/// bilinear transform away from Nyquist
+const MAX_FREQ_RATIO: f32 = 0.49;
+
+/// Response of one band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandSettings {
+    pub band_type: BandType,
+    pub freq: f32,
+    /// Ignored by the types without a gain
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+impl BandSettings {
+    /// Biquad with this response at `sample_rate`
+    pub fn design(&self, sample_rate: f32) -> BiquadState {
+        let freq = self.freq.min(MAX_FREQ_RATIO * sample_rate);
+        let mut biquad = BiquadState::default();
+        match self.band_type {
+            BandType::Bell => biquad.set_peaking(sample_rate, freq, self.q, self.gain_db),
+            BandType::LowShelf => biquad.set_lowshelf(sample_rate, freq, self.q, self.gain_db),
+            BandType::HighShelf => biquad.set_highshelf(sample_rate, freq, self.q, self.gain_db),
+            BandType::HighPass => biquad.set_highpass(sample_rate, freq, self.q),
+            BandType::LowPass => biquad.set_lowpass(sample_rate, freq, self.q),
+            BandType::Notch => biquad.set_notch(sample_rate, freq, self.q),
+        }
+        biquad
+    }
+}
+
+/// One EQ band over every channel, faded in and out when it is switched
+/// so enabling, disabling, soloing and changing its type do not click
+#[derive(Clone, Copy, Default)]
+pub struct Band {
+    filters: BiquadLanes<MAX_CHANNELS>,
+    /// Settings the filters were last designed with
+    designed: Option<BandSettings>,
+    /// Share of the filtered signal, 0 while the band is bypassed
+    level: f32,
+    target: f32,
+    /// Level change per sample
+    step: f32,
+    /// Whether the band is fading out to change its type
+    switching: bool,
+}
+
+impl Band {
+    /// Set the time a full fade in or out takes
+    pub fn set_fade_time(&mut self, sample_rate: f32, time_ms: f32) {
+        self.step = 1.0 / (0.001 * time_ms * sample_rate).max(1.0);
+    }
+
+    /// Start fading the band in or out. A band coming back from bypass
+    /// starts from silence rather than the state it was left with.
+    pub fn set_active(&mut self, active: bool) {
+        if active && self.target == 0.0 && self.level == 0.0 {
+            self.reset();
+        }
+        self.target = if active { 1.0 } else { 0.0 };
+    }
+
+    /// Redesign the filters, ramping to the new coefficients over
+    /// `ramp_samples` and skipping the redesign if the settings have not
+    /// moved. A new type would start from the state of the old one, so a
+    /// band being heard fades out first and comes back in from silence.
+    pub fn update(&mut self, settings: BandSettings, sample_rate: f32, ramp_samples: u32) {
+        let type_changed = self
+            .designed
+            .is_some_and(|designed| designed.band_type != settings.band_type);
+        self.switching = type_changed && self.level > 0.0;
+        if self.switching || self.designed == Some(settings) {
+            return;
+        }
+
+        let biquad = settings.design(sample_rate);
+        // Nothing is heard of a first design or a silent band, so they jump
+        if self.designed.is_none() || type_changed || self.level == 0.0 {
+            self.filters.copy_coefficients(&biquad);
+        } else {
+            self.filters.ramp_coefficients(&biquad, ramp_samples);
+        }
+        if type_changed {
+            self.filters.reset();
+        }
+        self.designed = Some(settings);
+    }
+
+    /// Advance the fade one sample and return the level to process with
+    #[inline]
+    pub fn next_level(&mut self) -> f32 {
+        let target = if self.switching { 0.0 } else { self.target };
+        self.level = if self.level < target {
+            (self.level + self.step).min(target)
+        } else {
+            (self.level - self.step).max(target)
+        };
+        self.level
+    }
+
+    /// Process one frame, one sample per channel, at the level from
+    /// `next_level`
+    #[inline]
+    pub fn process(&mut self, input: [f32; MAX_CHANNELS], level: f32) -> [f32; MAX_CHANNELS] {
+        if level == 0.0 {
+            return input;
+        }
+        let output = self.filters.process(input);
+        std::array::from_fn(|channel| input[channel] + (output[channel] - input[channel]) * level)
+    }
+
+    /// Clear the filter states
+    pub fn reset(&mut self) {
+        self.filters.reset();
+    }
+
+    /// Flush denormal state and reset any channel whose state went NaN or
+    /// infinite. Returns whether one was reset.
+    pub fn sanitize(&mut self) -> bool {
+        let mut recovered = false;
+        for (channel, finite) in self.filters.sanitize_state().into_iter().enumerate() {
+            if !finite {
+                self.filters.reset_lane(channel);
+                recovered = true;
+            }
+        }
+        recovered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FrequencyResponse;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn settings(band_type: BandType, freq: f32, gain_db: f32) -> BandSettings {
+        BandSettings {
+            band_type,
+            freq,
+            gain_db,
+            q: std::f32::consts::FRAC_1_SQRT_2,
+        }
+    }
+
+    #[test]
+    fn test_band_types_shape_the_response() {
+        let bell = settings(BandType::Bell, 1000.0, 6.0).design(SAMPLE_RATE);
+        assert!((bell.magnitude_db(SAMPLE_RATE, 1000.0) - 6.0).abs() < 0.01);
+        assert!(bell.magnitude_db(SAMPLE_RATE, 20.0).abs() < 0.05);
+
+        let shelf = settings(BandType::LowShelf, 200.0, -9.0).design(SAMPLE_RATE);
+        assert!((shelf.magnitude_db(SAMPLE_RATE, 20.0) + 9.0).abs() < 0.1);
+        assert!(shelf.magnitude_db(SAMPLE_RATE, 10_000.0).abs() < 0.05);
+
+        // The gain has no say over the pass and notch types
+        let notch = settings(BandType::Notch, 1000.0, 12.0).design(SAMPLE_RATE);
+        assert!(notch.magnitude_db(SAMPLE_RATE, 1000.0) < -60.0);
+        let highpass = settings(BandType::HighPass, 100.0, 12.0).design(SAMPLE_RATE);
+        assert!((highpass.magnitude_db(SAMPLE_RATE, 100.0) + 3.01).abs() < 0.05);
+        assert!(highpass.magnitude_db(SAMPLE_RATE, 10_000.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_band_fades_in_and_out() {
+        let mut band = Band::default();
+        band.set_fade_time(1000.0, 10.0);
+        band.update(settings(BandType::Bell, 100.0, 12.0), 1000.0, 0);
+
+        // Bypassed, the input passes untouched
+        let level = band.next_level();
+        assert_eq!(
+            band.process([0.5; MAX_CHANNELS], level),
+            [0.5; MAX_CHANNELS]
+        );
+
+        band.set_active(true);
+        let levels: Vec<f32> = (0..10).map(|_| band.next_level()).collect();
+        assert!((levels[4] - 0.5).abs() < 1e-6);
+        assert_eq!(levels[9], 1.0);
+
+        band.set_active(false);
+        for _ in 0..10 {
+            band.next_level();
+        }
+        let level = band.next_level();
+        assert_eq!(
+            band.process([0.25; MAX_CHANNELS], level),
+            [0.25; MAX_CHANNELS]
+        );
+    }
+
+    #[test]
+    fn test_type_change_fades_out_and_back_in() {
+        let mut band = Band::default();
+        band.set_fade_time(1000.0, 10.0);
+        band.update(settings(BandType::Bell, 100.0, 12.0), 1000.0, 0);
+        band.set_active(true);
+        for _ in 0..10 {
+            let level = band.next_level();
+            band.process([0.5; MAX_CHANNELS], level);
+        }
+
+        // The old type keeps running until the band is silent
+        let highpass = settings(BandType::HighPass, 100.0, 0.0);
+        band.update(highpass, 1000.0, 0);
+        assert_eq!(band.designed.unwrap().band_type, BandType::Bell);
+        let levels: Vec<f32> = (0..10).map(|_| band.next_level()).collect();
+        assert!(levels.windows(2).all(|pair| pair[1] < pair[0]));
+        assert_eq!(levels[9], 0.0);
+
+        // The new type starts from silence and fades back in
+        band.update(highpass, 1000.0, 0);
+        assert_eq!(band.designed, Some(highpass));
+        assert_eq!(
+            band.filters.process([0.0; MAX_CHANNELS]),
+            [0.0; MAX_CHANNELS]
+        );
+        let levels: Vec<f32> = (0..10).map(|_| band.next_level()).collect();
+        assert_eq!(levels[9], 1.0);
+    }
+
+    #[test]
+    fn test_settings_change_ramps_coefficients() {
+        let mut band = Band::default();
+        band.set_fade_time(SAMPLE_RATE, 1.0);
+        // A bell without gain passes its input untouched
+        band.update(settings(BandType::Bell, 1000.0, 0.0), SAMPLE_RATE, 16);
+        band.set_active(true);
+        while band.next_level() < 1.0 {}
+
+        let boost = settings(BandType::Bell, 1000.0, 12.0);
+        band.update(boost, SAMPLE_RATE, 16);
+        let level = band.next_level();
+        let [y, _] = band.process([1.0, 0.0], level);
+
+        // The first sample only moves a sixteenth of the way
+        let b0 = boost.design(SAMPLE_RATE).coefficients()[0];
+        assert!((y - (1.0 + (b0 - 1.0) / 16.0)).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,241 @@
+use editor_common::{
+    draw_grid, param_slider, PlotAxes, BACKGROUND, MIN_HEIGHT, MIN_WIDTH, WINDOW_HEIGHT,
+    WINDOW_WIDTH,
+};
+use nih_plug::prelude::*;
+use nih_plug_egui::egui::{
+    self, Align2, Color32, FontId, Pos2, RichText, Sense, Shape, Stroke, Vec2,
+};
+use nih_plug_egui::resizable_window::ResizableWindow;
+use nih_plug_egui::{create_egui_editor, EguiState};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::filter::{BiquadState, FrequencyResponse};
+use crate::{any_solo, BandParams, EditorData, ParametricEqParams, NUM_BANDS};
+
+/// Level range of the plot, which is also the range of the band gains
+const MIN_DB: f32 = -24.0;
+const MAX_DB: f32 = 24.0;
+
+/// Distance in points within which a node can be grabbed
+const NODE_RADIUS: f32 = 12.0;
+
+const CURVE: Color32 = Color32::from_rgb(240, 160, 60);
+const BAND_COLORS: [Color32; NUM_BANDS] = [
+    Color32::from_rgb(230, 90, 70),
+    Color32::from_rgb(240, 160, 60),
+    Color32::from_rgb(220, 210, 80),
+    Color32::from_rgb(90, 190, 110),
+    Color32::from_rgb(70, 170, 190),
+    Color32::from_rgb(90, 130, 230),
+    Color32::from_rgb(150, 120, 230),
+    Color32::from_rgb(220, 110, 190),
+];
+
+/// Band selection and the node being dragged
+#[derive(Default)]
+struct EditorState {
+    selected: usize,
+    dragging: Option<usize>,
+}
+
+pub fn default_state() -> Arc<EguiState> {
+    EguiState::from_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+}
+
+pub fn create(
+    params: Arc<ParametricEqParams>,
+    editor_data: Arc<EditorData>,
+    editor_state: Arc<EguiState>,
+) -> Option<Box<dyn Editor>> {
+    let window_state = editor_state.clone();
+
+    create_egui_editor(
+        editor_state,
+        EditorState::default(),
+        |_, _| {},
+        move |egui_ctx, setter, state| {
+            let sample_rate = f32::from_bits(editor_data.sample_rate.load(Ordering::Relaxed));
+
+            ResizableWindow::new("parametric-eq")
+                .min_size(Vec2::new(MIN_WIDTH, MIN_HEIGHT))
+                .show(egui_ctx, window_state.as_ref(), |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for (index, band) in params.bands.iter().enumerate() {
+                            let color = if band.enabled.value() {
+                                BAND_COLORS[index]
+                            } else {
+                                Color32::GRAY
+                            };
+                            let label = RichText::new(format!("{}", index + 1)).color(color);
+                            if ui
+                                .selectable_label(state.selected == index, label)
+                                .clicked()
+                            {
+                                state.selected = index;
+                            }
+                        }
+
+                        let band = &params.bands[state.selected];
+                        param_slider(ui, "On", &band.enabled, setter);
+                        param_slider(ui, "Solo", &band.solo, setter);
+                        param_slider(ui, "Type", &band.band_type, setter);
+                        param_slider(ui, "Freq", &band.freq, setter);
+                        param_slider(ui, "Gain", &band.gain, setter);
+                        param_slider(ui, "Q", &band.q, setter);
+                        param_slider(ui, "Output", &params.output_gain, setter);
+                    });
+                    ui.add_space(4.0);
+
+                    draw_plot(ui, &params, setter, state, sample_rate);
+                });
+        },
+    )
+}
+
+/// Level a band's node sits at: its gain, or 0 dB for the types without one
+fn node_db(band: &BandParams) -> f32 {
+    if band.band_type.value().uses_gain() {
+        band.gain.value()
+    } else {
+        0.0
+    }
+}
+
+/// Combined response curve, the selected band's own curve and a node per
+/// band. Drag a node sideways for frequency and up and down for gain, or
+/// for Q on the types without a gain; scroll over it for Q.
+fn draw_plot(
+    ui: &mut egui::Ui,
+    params: &ParametricEqParams,
+    setter: &ParamSetter,
+    state: &mut EditorState,
+    sample_rate: f32,
+) {
+    let (rect, drag) = ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, BACKGROUND);
+
+    let axes = PlotAxes::new(rect, MIN_DB, MAX_DB);
+    draw_grid(&painter, &axes, 6.0);
+
+    let curve = |biquads: &[BiquadState]| {
+        axes.curve(|freq| {
+            biquads
+                .iter()
+                .map(|biquad| biquad.magnitude_db(sample_rate, freq))
+                .sum()
+        })
+    };
+
+    let any_solo = any_solo(&params.bands);
+    let active: Vec<BiquadState> = params
+        .bands
+        .iter()
+        .filter(|band| band.is_active(any_solo))
+        .map(|band| band.settings().design(sample_rate))
+        .collect();
+    let selected = &params.bands[state.selected];
+    painter.add(Shape::line(
+        curve(&[selected.settings().design(sample_rate)]),
+        Stroke::new(1.0, BAND_COLORS[state.selected].gamma_multiply(0.6)),
+    ));
+    painter.add(Shape::line(curve(&active), Stroke::new(2.0, CURVE)));
+
+    let nodes: Vec<Pos2> = params
+        .bands
+        .iter()
+        .map(|band| {
+            Pos2::new(
+                axes.freq_to_x(band.freq.value()),
+                axes.db_to_y(node_db(band)),
+            )
+        })
+        .collect();
+    let nearest = |pos: Pos2| {
+        (0..NUM_BANDS)
+            .filter(|&index| nodes[index].distance(pos) < NODE_RADIUS)
+            .min_by(|&a, &b| nodes[a].distance(pos).total_cmp(&nodes[b].distance(pos)))
+    };
+    let hovered = drag.hover_pos().and_then(nearest);
+
+    for (index, (band, &node)) in params.bands.iter().zip(&nodes).enumerate() {
+        let color = BAND_COLORS[index];
+        let radius = if hovered == Some(index) || state.dragging == Some(index) {
+            8.0
+        } else {
+            6.0
+        };
+        if band.enabled.value() {
+            painter.circle_filled(node, radius, color);
+        } else {
+            painter.circle_stroke(node, radius, Stroke::new(1.5, color));
+        }
+        if index == state.selected {
+            painter.circle_stroke(node, radius + 2.0, Stroke::new(1.5, Color32::WHITE));
+        }
+        painter.text(
+            node - Vec2::new(0.0, radius + 2.0),
+            Align2::CENTER_BOTTOM,
+            format!("{}", index + 1),
+            FontId::proportional(11.0),
+            color,
+        );
+    }
+
+    if drag.clicked() {
+        if let Some(index) = hovered {
+            state.selected = index;
+        }
+    }
+
+    // Dragging a disabled node brings it back
+    if drag.drag_started() {
+        state.dragging = drag.interact_pointer_pos().and_then(nearest);
+        if let Some(index) = state.dragging {
+            let band = &params.bands[index];
+            state.selected = index;
+            if !band.enabled.value() {
+                setter.begin_set_parameter(&band.enabled);
+                setter.set_parameter(&band.enabled, true);
+                setter.end_set_parameter(&band.enabled);
+            }
+            setter.begin_set_parameter(&band.freq);
+            setter.begin_set_parameter(&band.gain);
+            setter.begin_set_parameter(&band.q);
+        }
+    }
+    if let Some(band) = state.dragging.map(|index| &params.bands[index]) {
+        if drag.dragged() {
+            if let Some(pos) = drag.interact_pointer_pos() {
+                setter.set_parameter(&band.freq, axes.x_to_freq(pos.x));
+                if band.band_type.value().uses_gain() {
+                    setter.set_parameter(&band.gain, axes.y_to_db(pos.y));
+                }
+            }
+            if !band.band_type.value().uses_gain() {
+                let q = (band.q.unmodulated_normalized_value()
+                    - drag.drag_delta().y / rect.height())
+                .clamp(0.0, 1.0);
+                setter.set_parameter_normalized(&band.q, q);
+            }
+        }
+        if drag.drag_stopped() {
+            setter.end_set_parameter(&band.q);
+            setter.end_set_parameter(&band.gain);
+            setter.end_set_parameter(&band.freq);
+            state.dragging = None;
+        }
+    }
+
+    if let Some(band) = hovered.map(|index| &params.bands[index]) {
+        let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+        if scroll != 0.0 {
+            let q = (band.q.unmodulated_normalized_value() + scroll * 0.002).clamp(0.0, 1.0);
+            setter.begin_set_parameter(&band.q);
+            setter.set_parameter_normalized(&band.q, q);
+            setter.end_set_parameter(&band.q);
+        }
+    }
+}
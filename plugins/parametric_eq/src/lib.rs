@@ -0,0 +1,421 @@
+use nih_plug::prelude::*;
+use nih_plug_egui::EguiState;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+mod band;
+mod editor;
+use band::{Band, BandSettings};
+use filter_dsp::{filter, lanes};
+
+/// Number of EQ bands
+pub const NUM_BANDS: usize = 8;
+
+/// Channels of the widest supported layout
+const MAX_CHANNELS: usize = 2;
+
+/// Samples between coefficient updates while a band is being smoothed.
+/// Each update ramps over the interval.
+const COEFFICIENT_INTERVAL: u32 = 16;
+
+/// Time a band takes to fade in or out when it is switched
+const BAND_FADE_MS: f32 = 10.0;
+
+/// Type, frequency and whether the band starts enabled, from the lowest
+/// band up
+const DEFAULT_BANDS: [(BandType, f32, bool); NUM_BANDS] = [
+    (BandType::HighPass, 30.0, false),
+    (BandType::LowShelf, 100.0, true),
+    (BandType::Bell, 250.0, true),
+    (BandType::Bell, 600.0, true),
+    (BandType::Bell, 1500.0, true),
+    (BandType::Bell, 4000.0, true),
+    (BandType::HighShelf, 8000.0, true),
+    (BandType::LowPass, 18_000.0, false),
+];
+
+/// Filter shape of a band
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BandType {
+    #[id = "bell"]
+    #[name = "Bell"]
+    #[default]
+    Bell,
+    #[id = "lowshelf"]
+    #[name = "Low Shelf"]
+    LowShelf,
+    #[id = "highshelf"]
+    #[name = "High Shelf"]
+    HighShelf,
+    #[id = "highpass"]
+    #[name = "High-Pass"]
+    HighPass,
+    #[id = "lowpass"]
+    #[name = "Low-Pass"]
+    LowPass,
+    #[id = "notch"]
+    #[name = "Notch"]
+    Notch,
+}
+
+impl BandType {
+    /// Whether the gain parameter shapes the response
+    pub fn uses_gain(self) -> bool {
+        matches!(self, Self::Bell | Self::LowShelf | Self::HighShelf)
+    }
+}
+
+/// Plugin parameters
+#[derive(Params)]
+struct ParametricEqParams {
+    #[persist = "editor-state"]
+    pub editor_state: Arc<EguiState>,
+
+    #[nested(array, group = "Band")]
+    pub bands: [BandParams; NUM_BANDS],
+
+    #[id = "output_gain"]
+    pub output_gain: FloatParam,
+}
+
+/// Parameters of one band
+#[derive(Params)]
+struct BandParams {
+    #[id = "enabled"]
+    pub enabled: BoolParam,
+
+    /// While any band is soloed, only the soloed bands are heard
+    #[id = "solo"]
+    pub solo: BoolParam,
+
+    #[id = "type"]
+    pub band_type: EnumParam<BandType>,
+
+    #[id = "freq"]
+    pub freq: FloatParam,
+
+    /// Only used by the bell and shelf types
+    #[id = "gain"]
+    pub gain: FloatParam,
+
+    #[id = "q"]
+    pub q: FloatParam,
+}
+
+impl Default for ParametricEqParams {
+    fn default() -> Self {
+        Self {
+            editor_state: editor::default_state(),
+
+            bands: std::array::from_fn(BandParams::new),
+
+            output_gain: FloatParam::new(
+                "Output",
+                0.0,
+                FloatRange::Linear {
+                    min: -24.0,
+                    max: 24.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1))
+            .with_smoother(SmoothingStyle::Linear(50.0)),
+        }
+    }
+}
+
+impl BandParams {
+    /// Parameters of the band at `index`, counting from the lowest
+    fn new(index: usize) -> Self {
+        let (band_type, freq, enabled) = DEFAULT_BANDS[index];
+        let name = |param: &str| format!("Band {} {}", index + 1, param);
+
+        Self {
+            enabled: BoolParam::new(name("Enabled"), enabled),
+
+            solo: BoolParam::new(name("Solo"), false),
+
+            band_type: EnumParam::new(name("Type"), band_type),
+
+            freq: FloatParam::new(
+                name("Frequency"),
+                freq,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(2))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz())
+            .with_smoother(SmoothingStyle::Logarithmic(50.0)),
+
+            gain: FloatParam::new(
+                name("Gain"),
+                0.0,
+                FloatRange::Linear {
+                    min: -24.0,
+                    max: 24.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1))
+            .with_smoother(SmoothingStyle::Linear(50.0)),
+
+            q: FloatParam::new(
+                name("Q"),
+                0.707,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 18.0,
+                    factor: FloatRange::skew_factor(-1.5),
+                },
+            )
+            .with_unit(" Q")
+            .with_value_to_string(formatters::v2s_f32_rounded(2))
+            .with_smoother(SmoothingStyle::Logarithmic(50.0)),
+        }
+    }
+
+    /// Band settings at the unsmoothed parameter values
+    fn settings(&self) -> BandSettings {
+        BandSettings {
+            band_type: self.band_type.value(),
+            freq: self.freq.value(),
+            gain_db: self.gain.value(),
+            q: self.q.value(),
+        }
+    }
+
+    /// Whether the band is heard, given whether any band is soloed
+    fn is_active(&self, any_solo: bool) -> bool {
+        self.enabled.value() && (!any_solo || self.solo.value())
+    }
+}
+
+/// Whether any band of `bands` is soloed
+fn any_solo(bands: &[BandParams]) -> bool {
+    bands.iter().any(|band| band.solo.value())
+}
+
+/// State the audio thread shares with the editor
+#[derive(Default)]
+struct EditorData {
+    /// Bits of the sample rate the bands are designed at
+    sample_rate: AtomicU32,
+}
+
+/// Eight-band parametric EQ plugin
+struct ParametricEq {
+    params: Arc<ParametricEqParams>,
+    sample_rate: f32,
+    bands: [Band; NUM_BANDS],
+    coefficient_countdown: u32,
+    editor_data: Arc<EditorData>,
+}
+
+impl Default for ParametricEq {
+    fn default() -> Self {
+        let editor_data = EditorData::default();
+        editor_data
+            .sample_rate
+            .store(44100.0f32.to_bits(), Ordering::Relaxed);
+
+        Self {
+            params: Arc::new(ParametricEqParams::default()),
+            sample_rate: 44100.0,
+            bands: [Band::default(); NUM_BANDS],
+            coefficient_countdown: 0,
+            editor_data: Arc::new(editor_data),
+        }
+    }
+}
+
+impl Plugin for ParametricEq {
+    const NAME: &'static str = "Parametric EQ";
+    const VENDOR: &'static str = "trwolf";
+    const URL: &'static str = "";
+    const EMAIL: &'static str = "";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        // Stereo
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+            ..AudioIOLayout::const_default()
+        },
+        // Mono
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(1),
+            main_output_channels: NonZeroU32::new(1),
+            ..AudioIOLayout::const_default()
+        },
+    ];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        editor::create(
+            self.params.clone(),
+            self.editor_data.clone(),
+            self.params.editor_state.clone(),
+        )
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        buffer_config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.sample_rate = buffer_config.sample_rate;
+        self.editor_data
+            .sample_rate
+            .store(self.sample_rate.to_bits(), Ordering::Relaxed);
+
+        self.bands = [Band::default(); NUM_BANDS];
+        for band in &mut self.bands {
+            band.set_fade_time(self.sample_rate, BAND_FADE_MS);
+        }
+        self.coefficient_countdown = 0;
+
+        true
+    }
+
+    fn reset(&mut self) {
+        for band in &mut self.bands {
+            band.reset();
+        }
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        let any_solo = any_solo(&self.params.bands);
+        for (band, params) in self.bands.iter_mut().zip(&self.params.bands) {
+            band.set_active(params.is_active(any_solo));
+        }
+
+        for mut channel_samples in buffer.iter_samples() {
+            // Smoothers advance every sample, the coefficients follow every
+            // few samples
+            let refresh = self.coefficient_countdown == 0;
+            self.coefficient_countdown = if refresh {
+                COEFFICIENT_INTERVAL - 1
+            } else {
+                self.coefficient_countdown - 1
+            };
+
+            let mut levels = [0.0; NUM_BANDS];
+            for ((band, params), level) in self
+                .bands
+                .iter_mut()
+                .zip(&self.params.bands)
+                .zip(&mut levels)
+            {
+                let settings = BandSettings {
+                    band_type: params.band_type.value(),
+                    freq: params.freq.smoothed.next(),
+                    gain_db: params.gain.smoothed.next(),
+                    q: params.q.smoothed.next(),
+                };
+                if refresh {
+                    band.update(settings, self.sample_rate, COEFFICIENT_INTERVAL);
+                }
+                *level = band.next_level();
+            }
+            let output_gain = util::db_to_gain(self.params.output_gain.smoothed.next());
+
+            let mut frame = [0.0; MAX_CHANNELS];
+            for (value, sample) in frame.iter_mut().zip(channel_samples.iter_mut()) {
+                *value = *sample;
+            }
+            for (band, &level) in self.bands.iter_mut().zip(&levels) {
+                frame = band.process(frame, level);
+            }
+            for (sample, value) in channel_samples.into_iter().zip(frame) {
+                *sample = value * output_gain;
+            }
+        }
+
+        let mut recovered = false;
+        for band in &mut self.bands {
+            recovered |= band.sanitize();
+        }
+        if recovered {
+            for channel in buffer.as_slice() {
+                for sample in channel.iter_mut().filter(|sample| !sample.is_finite()) {
+                    *sample = 0.0;
+                }
+            }
+        }
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for ParametricEq {
+    const CLAP_ID: &'static str = "com.trwolf.parametric-eq";
+    const CLAP_DESCRIPTION: Option<&'static str> = Some("An eight-band parametric EQ");
+    const CLAP_MANUAL_URL: Option<&'static str> = None;
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Equalizer,
+        ClapFeature::Filter,
+        ClapFeature::Stereo,
+        ClapFeature::Mono,
+    ];
+}
+
+impl Vst3Plugin for ParametricEq {
+    const VST3_CLASS_ID: [u8; 16] = *b"TrwolfParamEQ8b!";
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Eq];
+}
+
+nih_export_clap!(ParametricEq);
+nih_export_vst3!(ParametricEq);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn band(enabled: bool, solo: bool) -> BandParams {
+        BandParams {
+            enabled: BoolParam::new("Enabled", enabled),
+            solo: BoolParam::new("Solo", solo),
+            ..BandParams::new(0)
+        }
+    }
+
+    #[test]
+    fn test_solo_leaves_only_soloed_bands_heard() {
+        // Without a solo every enabled band is heard
+        let bands = [band(true, false), band(false, false), band(true, false)];
+        assert!(!any_solo(&bands));
+        let active: Vec<bool> = bands.iter().map(|band| band.is_active(false)).collect();
+        assert_eq!(active, [true, false, true]);
+
+        let bands = [band(true, false), band(false, true), band(true, true)];
+        let any_solo = any_solo(&bands);
+        assert!(any_solo);
+        let active: Vec<bool> = bands.iter().map(|band| band.is_active(any_solo)).collect();
+        // Soloing does not enable a disabled band
+        assert_eq!(active, [false, false, true]);
+    }
+}